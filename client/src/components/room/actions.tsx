@@ -98,7 +98,7 @@ const DuringGameActions = ({
 
   return (
     <>
-      {mode === "Online" ? (
+      {mode !== "Local" ? (
         <Button
           className={`w-1/2 ${drawStatus === GameAction.Requested ? "animate-pulse" : ""}`}
           isDisabled={drawStatus === GameAction.Sent}
@@ -118,7 +118,7 @@ const DuringGameActions = ({
               : "Request Draw"}
         </Button>
       ) : (
-        <Tooltip content="Draw is available in online matches">
+        <Tooltip content="Draw is available against an opponent or the bot">
           <span className="w-1/2">
             <Button isDisabled className="w-full" variant="flat">
               Request Draw
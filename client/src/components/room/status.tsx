@@ -3,7 +3,7 @@ import { Button } from "@heroui/button";
 import { Modal, ModalBody, ModalContent } from "@heroui/modal";
 import { cn } from "@heroui/theme";
 
-import { BoardStatus } from "@/types";
+import { BoardStatus, BotStrength } from "@/types";
 import { GameAction } from "@/types/actions";
 import { GameStore, RoomStore } from "@/store";
 import { Player } from "@/types/player";
@@ -15,6 +15,9 @@ interface GameStatusProps {
   player: Player;
   drawStatus: GameAction | null;
   score: [number, number];
+  gameNumber: number;
+  matchResult: BoardStatus | null;
+  botStrength: BotStrength | null;
   playerNames?: {
     player1?: string;
     player2?: string;
@@ -23,6 +26,9 @@ interface GameStatusProps {
 
 interface ScoreBoardProps {
   score: [number, number];
+  gameNumber: number;
+  matchResult: BoardStatus | null;
+  botStrength: BotStrength | null;
   player: Player;
   playerNames?: {
     player1?: string;
@@ -59,6 +65,9 @@ const GameStatus = ({
   rematchStatus,
   player,
   score,
+  gameNumber,
+  matchResult,
+  botStrength,
   boardStatus,
   playerNames,
 }: GameStatusProps) => {
@@ -71,7 +80,14 @@ const GameStatus = ({
 
   return (
     <>
-      <ScoreBoard player={player} playerNames={playerNames} score={score} />
+      <ScoreBoard
+        botStrength={botStrength}
+        gameNumber={gameNumber}
+        matchResult={matchResult}
+        player={player}
+        playerNames={playerNames}
+        score={score}
+      />
       <RematchModal
         openModal={currentOpenModal}
         rematch={handleRematch}
@@ -112,13 +128,24 @@ const ModalFrame = ({
   </ModalContent>
 );
 
-const ScoreBoard = ({ player, score, playerNames }: ScoreBoardProps) => {
+const ScoreBoard = ({
+  player,
+  score,
+  gameNumber,
+  matchResult,
+  botStrength,
+  playerNames,
+}: ScoreBoardProps) => {
   const { mode } = RoomStore();
 
   const localLeftLabel = playerNames?.player1 ?? "Player 1";
   const localRightLabel = playerNames?.player2 ?? "Player 2";
   const botUserName = playerNames?.player1 ?? "You";
 
+  const botLabel = botStrength
+    ? `Bot · ${botStrength.skill.toFixed(1)}`
+    : "Bot";
+
   const leftLabel =
     mode === "Online"
       ? player.marker === "X"
@@ -127,7 +154,7 @@ const ScoreBoard = ({ player, score, playerNames }: ScoreBoardProps) => {
       : mode === "Bot"
         ? player.marker === "X"
           ? botUserName
-          : "Bot"
+          : botLabel
         : localLeftLabel;
   const rightLabel =
     mode === "Online"
@@ -137,9 +164,16 @@ const ScoreBoard = ({ player, score, playerNames }: ScoreBoardProps) => {
       : mode === "Bot"
         ? player.marker === "O"
           ? botUserName
-          : "Bot"
+          : botLabel
         : localRightLabel;
-  const round = score[0] + score[1] + 1;
+  const matchLabel =
+    matchResult === BoardStatus.Draw
+      ? "Match drawn"
+      : matchResult === BoardStatus.X
+        ? `${leftLabel} takes the match`
+        : matchResult === BoardStatus.O
+          ? `${rightLabel} takes the match`
+          : null;
 
   return (
     <div className="border-foreground-100/70 bg-content1/85 mx-auto grid w-full max-w-2xl grid-cols-3 items-center rounded-2xl border shadow-lg">
@@ -157,7 +191,7 @@ const ScoreBoard = ({ player, score, playerNames }: ScoreBoardProps) => {
           {score[0]} : {score[1]}
         </p>
         <p className="text-foreground-500 mt-1 text-xs font-bold tracking-[0.12em] uppercase">
-          Round {round}
+          {matchLabel ?? `Round ${gameNumber}`}
         </p>
       </div>
 
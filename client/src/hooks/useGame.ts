@@ -3,6 +3,7 @@ import { useEffect, useRef, useState } from "react";
 import {
   Board,
   BoardStatus,
+  BotStrength,
   socketEvent,
   RoomStatus,
   PlayerAction,
@@ -38,6 +39,9 @@ const useGame = () => {
   const [rematchStatus, setRematchStatus] = useState<GameAction | null>(null);
   const [drawStatus, setDrawStatus] = useState<GameAction | null>(null);
   const [score, setScore] = useState<[number, number]>([0, 0]);
+  const [gameNumber, setGameNumber] = useState(1);
+  const [matchResult, setMatchResult] = useState<BoardStatus | null>(null);
+  const [botStrength, setBotStrength] = useState<BotStrength | null>(null);
 
   const [board, setBoard] = useState<{
     boards: Board;
@@ -83,15 +87,6 @@ const useGame = () => {
       try {
         e = JSON.parse(event.data);
       } catch {
-        if (
-          typeof event.data === "string" &&
-          event.data.includes("Invalid room password")
-        ) {
-          if (!unmounted) {
-            updateStatus(RoomStatus.authFailed, "Invalid password");
-          }
-        }
-
         return;
       }
       const eventName = e.event;
@@ -156,6 +151,11 @@ const useGame = () => {
 
           setBoard(e.data.board);
           setScore(e.data.score);
+          setGameNumber(e.data.game_number);
+          setBotStrength(e.data.bot_strength);
+          if (e.data.game_number === 1 && e.data.board.status === null) {
+            setMatchResult(null);
+          }
 
           setMove({
             nextMove: e.data.next_board,
@@ -164,6 +164,11 @@ const useGame = () => {
           setNextPlayer(e.data.next_player.marker);
           break;
 
+        case "MatchOver":
+          setMatchResult(e.data.result);
+          setScore(e.data.score);
+          break;
+
         case "PlayerUpdate": {
           const action = e.data.action as PlayerAction;
           const isDisconnectedObject =
@@ -192,6 +197,12 @@ const useGame = () => {
             break;
           }
 
+          if (action === "MarkerChanged") {
+            playerMarker = e.data.player.marker;
+            setPlayer(e.data.player);
+            break;
+          }
+
           if (action === "Joined" || action === "Reconnected") {
             setDisconnectOwner(null);
             setOpponentReconnectSeconds(null);
@@ -282,14 +293,14 @@ const useGame = () => {
           break;
 
         case "Error":
-          if (e.data.error === "InvalidPassword") {
+          if (e.data.code === "ROOM_INVALID_PASSWORD") {
             if (!unmounted) {
               updateStatus(RoomStatus.authFailed, "Invalid password");
             }
             break;
           }
           if (!unmounted) {
-            updateStatus(RoomStatus.internal, e.data.error);
+            updateStatus(RoomStatus.internal, e.data.message);
           }
           break;
 
@@ -342,6 +353,9 @@ const useGame = () => {
     ws,
     board,
     score,
+    gameNumber,
+    matchResult,
+    botStrength,
     status,
     rematchStatus,
     drawStatus,
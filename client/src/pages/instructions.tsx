@@ -530,7 +530,7 @@ function Instructions() {
                     },
                     {
                       q: "Can I offer a draw to my opponent?",
-                      a: 'In online matches, yes. Use "Request Draw" to offer; your opponent can accept or decline, and an unanswered offer auto-declines after 10 seconds. Draws by agreement end the game immediately.',
+                      a: 'In online and bot matches, yes. Use "Request Draw" to offer; your opponent can accept or decline, and an unanswered offer auto-declines after 10 seconds. The bot decides based on how it rates its position. Draws by agreement end the game immediately.',
                     },
                     {
                       q: "What happens when I resign?",
@@ -42,6 +42,9 @@ function RoomPage() {
     board,
     status,
     score,
+    gameNumber,
+    matchResult,
+    botStrength,
     rematchStatus,
     drawStatus,
     setStatus,
@@ -125,7 +128,7 @@ function RoomPage() {
       const room_id = sessionStorage.getItem("roomId");
       const player_id = sessionStorage.getItem("playerId");
 
-      const params = [];
+      const params = ["version=2"];
 
       if (room_id && room_id === roomId) {
         params.push(`is_reconnecting=true`);
@@ -141,7 +144,7 @@ function RoomPage() {
 
       setWs(
         new WebSocket(
-          `/ws/${roomId}${params.length >= 1 ? `?${params.join("&")}` : ""}`,
+          `/ws/${roomId}?${params.join("&")}`,
         ),
       );
     },
@@ -515,6 +518,9 @@ function RoomPage() {
               player={player}
               playerNames={playerNamesState}
               rematchStatus={rematchStatus}
+              gameNumber={gameNumber}
+              botStrength={botStrength}
+              matchResult={matchResult}
               score={score}
             />
             <div className="flex min-h-0 flex-1 items-center justify-center gap-3 lg:gap-4">
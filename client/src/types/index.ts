@@ -55,8 +55,18 @@ export type PlayerAction =
   | "Joined"
   | "Left"
   | "Reconnected"
+  | "MarkerChanged"
   | { Disconnected: number };
 
+export type BotStrength = {
+  skill: number;
+  max_depth: number;
+  max_time_ms: number;
+  random_move_chance: number;
+  mistake_chance: number;
+  engine: "Minimax" | "Random";
+};
+
 export type socketEvent =
   | {
       event: "GameUpdate";
@@ -66,6 +76,16 @@ export type socketEvent =
         next_board: number | null;
         last_move: [number, number] | null;
         score: [number, number];
+        game_number: number;
+        bot_strength: BotStrength | null;
+      };
+    }
+  | {
+      event: "MatchOver";
+      data: {
+        result: BoardStatus;
+        score: [number, number];
+        results: BoardStatus[];
       };
     }
   | {
@@ -99,7 +119,9 @@ export type socketEvent =
   | {
       event: "Error";
       data: {
-        error: string;
+        code: string;
+        message: string;
+        details?: Record<string, unknown>;
       };
     }
   | {
@@ -6,22 +6,9 @@ use std::{
 };
 use ultimatexo_core::{
     AppError,
-    models::{Board, GameState, MacroBoard, Marker, Status},
+    models::{Board, BoardSize, BotStrength, GameState, MacroBoard, Marker, RuleSet, Status},
 };
 
-const MAX_BOARDS: usize = 9;
-const MAX_CELLS: usize = 9;
-const WINNING_LINES: [[usize; 3]; 8] = [
-    [0, 1, 2],
-    [3, 4, 5],
-    [6, 7, 8],
-    [0, 3, 6],
-    [1, 4, 7],
-    [2, 5, 8],
-    [0, 4, 8],
-    [2, 4, 6],
-];
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub struct DifficultyLevel(u8);
 
@@ -99,6 +86,42 @@ impl AIConfig {
     }
 }
 
+impl AIConfig {
+    /// Settings for a fractional skill between 1.0 and 10.0. Depth, time and
+    /// the random and mistake chances are interpolated between the two
+    /// neighbouring levels; everything else comes from the nearer one.
+    pub fn from_skill(skill: f64) -> Self {
+        let skill = skill.clamp(1.0, 10.0);
+        let lower = Self::from_level(DifficultyLevel(skill.floor() as u8));
+        let upper = Self::from_level(DifficultyLevel(skill.ceil() as u8));
+        let t = skill.fract();
+        let lerp = |a: f64, b: f64| a + (b - a) * t;
+        let time = |config: &Self| config.max_time.unwrap_or_default().as_secs_f64();
+
+        let mut config = if t < 0.5 {
+            lower.clone()
+        } else {
+            upper.clone()
+        };
+        config.max_depth = lerp(lower.max_depth as f64, upper.max_depth as f64).round() as usize;
+        config.max_time = Some(Duration::from_secs_f64(lerp(time(&lower), time(&upper))));
+        config.random_move_chance = lerp(lower.random_move_chance, upper.random_move_chance);
+        config.mistake_chance = lerp(lower.mistake_chance, upper.mistake_chance);
+        config
+    }
+
+    /// Settings for `strength`'s skill, with its search limits and chances
+    /// taking precedence.
+    pub fn from_strength(strength: &BotStrength) -> Self {
+        let mut config = Self::from_skill(strength.skill);
+        config.max_depth = strength.max_depth;
+        config.max_time = Some(Duration::from_millis(strength.max_time_ms));
+        config.random_move_chance = strength.random_move_chance;
+        config.mistake_chance = strength.mistake_chance;
+        config
+    }
+}
+
 impl Default for AIConfig {
     fn default() -> Self {
         Self::from_level(DifficultyLevel::default())
@@ -125,6 +148,7 @@ pub struct EvaluationWeights {
     pub fork_bonus: i32,
     pub next_board_penalty: i32,
     pub mobility_bonus: i32,
+    pub board_majority: i32,
 }
 
 impl EvaluationWeights {
@@ -149,6 +173,7 @@ impl EvaluationWeights {
             fork_bonus: 1000 + scale * 150,
             next_board_penalty: 8 + scale,
             mobility_bonus: 1 + (scale / 3),
+            board_majority: 150 + scale * 25,
         }
     }
 }
@@ -222,30 +247,43 @@ impl StateEvaluator {
             _ => {}
         }
 
-        let mut score = 0;
-        let board_values = self.calculate_board_values(&state.board, ai_player);
+        let rules = state.rules;
+        // Under misère rules owning lines is a liability, so the same
+        // features are scored with the opposite sign.
+        let macro_sign = if rules.misere { -1 } else { 1 };
+        let micro_sign = macro_sign * if rules.misere_sub_boards { -1 } else { 1 };
+
+        let mut macro_score = 0;
+        let mut micro_score = 0;
+        let board_values = self.calculate_board_values(&state.board, rules, ai_player);
 
         for (i, board) in state.board.boards.iter().enumerate() {
             match board.status {
                 Status::Won(winner) if winner == ai_player => {
-                    score += board_values[i];
+                    macro_score += board_values[i];
                 }
                 Status::Won(winner) if winner == opponent => {
-                    score -= board_values[i];
+                    macro_score -= board_values[i];
                 }
                 Status::InProgress => {
-                    let micro_score = self.evaluate_micro_board(board, ai_player);
-                    score += (micro_score * board_values[i]) / 100;
+                    let board_score = self.evaluate_micro_board(board, rules.board_size, ai_player);
+                    micro_score += (board_score * board_values[i]) / 100;
                 }
                 _ => {}
             }
         }
 
-        score += self.evaluate_macro_position(&state.board, ai_player);
-        score += self.evaluate_forks(&state.board, ai_player);
+        macro_score += self.evaluate_macro_position(&state.board, rules, ai_player);
+        macro_score += self.evaluate_forks(&state.board, rules, ai_player);
+        if rules.majority_tiebreak {
+            macro_score += self.evaluate_majority(&state.board, ai_player);
+        }
+
+        let mut score = macro_score * macro_sign + micro_score * micro_sign;
 
         if let Some(next_board) = state.next_board {
-            score += self.evaluate_next_board(&state.board, next_board, opponent);
+            score +=
+                self.evaluate_next_board(&state.board, rules, next_board, opponent, micro_sign);
         }
 
         let move_count = MoveGenerator::count_moves(state);
@@ -254,27 +292,33 @@ impl StateEvaluator {
         score
     }
 
-    fn calculate_board_values(&mut self, board: &Board, ai_player: Marker) -> [i32; MAX_BOARDS] {
-        let mut values = [0i32; MAX_BOARDS];
+    fn calculate_board_values(
+        &mut self,
+        board: &Board,
+        rules: RuleSet,
+        ai_player: Marker,
+    ) -> Vec<i32> {
+        let size = rules.board_size;
         let w = &self.weights;
+        let critical = size.side() as i32 - 1;
+
+        let mut values: Vec<i32> = (0..size.cells())
+            .map(|i| {
+                if size.is_center(i) {
+                    w.center_board
+                } else if size.corners().contains(&i) {
+                    w.corner_board
+                } else {
+                    w.edge_board
+                }
+            })
+            .collect();
 
-        let base = [
-            w.corner_board,
-            w.edge_board,
-            w.corner_board,
-            w.edge_board,
-            w.center_board,
-            w.edge_board,
-            w.corner_board,
-            w.edge_board,
-            w.corner_board,
-        ];
-
-        for line in &WINNING_LINES {
-            let (ai_won, opp_won, _) = self.count_line_status(board, line, ai_player);
+        for line in size.lines() {
+            let (ai_won, opp_won, _) = self.count_line_status(board, rules, line, ai_player);
 
             if ai_won > 0 && opp_won == 0 {
-                let bonus = if ai_won == 2 {
+                let bonus = if ai_won == critical {
                     w.board_in_critical_line
                 } else {
                     w.board_in_threat_line
@@ -288,7 +332,7 @@ impl StateEvaluator {
             }
 
             if opp_won > 0 && ai_won == 0 {
-                let penalty = if opp_won == 2 {
+                let penalty = if opp_won == critical {
                     w.board_in_critical_line
                 } else {
                     w.board_in_threat_line / 2
@@ -302,32 +346,30 @@ impl StateEvaluator {
             }
         }
 
-        for i in 0..MAX_BOARDS {
-            values[i] += base[i];
-        }
-
         values
     }
 
-    fn evaluate_macro_position(&self, board: &Board, ai_player: Marker) -> i32 {
+    fn evaluate_macro_position(&self, board: &Board, rules: RuleSet, ai_player: Marker) -> i32 {
         let mut score = 0;
         let w = &self.weights;
+        let critical = rules.board_size.side() as i32 - 1;
 
-        for line in &WINNING_LINES {
-            let (ai_won, opp_won, in_progress) = self.count_line_status(board, line, ai_player);
+        for line in rules.board_size.lines() {
+            let (ai_won, opp_won, in_progress) =
+                self.count_line_status(board, rules, line, ai_player);
 
             if opp_won == 0 && ai_won > 0 {
                 score += match (ai_won, in_progress) {
-                    (2, 1) => w.macro_two_in_row,
-                    (1, _) => w.macro_one_in_row,
+                    (won, 1) if won == critical => w.macro_two_in_row,
+                    (won, _) if won < critical => w.macro_one_in_row,
                     _ => 0,
                 };
             }
 
             if ai_won == 0 && opp_won > 0 {
                 score -= match (opp_won, in_progress) {
-                    (2, 1) => w.macro_block_two,
-                    (1, _) => w.macro_block_one,
+                    (won, 1) if won == critical => w.macro_block_two,
+                    (won, _) if won < critical => w.macro_block_one,
                     _ => 0,
                 };
             }
@@ -336,17 +378,19 @@ impl StateEvaluator {
         score
     }
 
-    fn evaluate_forks(&self, board: &Board, ai_player: Marker) -> i32 {
+    fn evaluate_forks(&self, board: &Board, rules: RuleSet, ai_player: Marker) -> i32 {
         let mut ai_threats = 0;
         let mut opp_threats = 0;
+        let critical = rules.board_size.side() as i32 - 1;
 
-        for line in &WINNING_LINES {
-            let (ai_won, opp_won, available) = self.count_line_status(board, line, ai_player);
+        for line in rules.board_size.lines() {
+            let (ai_won, opp_won, available) =
+                self.count_line_status(board, rules, line, ai_player);
 
-            if ai_won == 2 && opp_won == 0 && available == 1 {
+            if ai_won == critical && opp_won == 0 && available == 1 {
                 ai_threats += 1;
             }
-            if opp_won == 2 && ai_won == 0 && available == 1 {
+            if opp_won == critical && ai_won == 0 && available == 1 {
                 opp_threats += 1;
             }
         }
@@ -366,49 +410,82 @@ impl StateEvaluator {
         fork_bonus - fork_penalty
     }
 
-    fn evaluate_next_board(&self, board: &Board, next_board: usize, opponent: Marker) -> i32 {
-        if next_board >= MAX_BOARDS
-            || !matches!(board.boards[next_board].status, Status::InProgress)
-        {
+    /// Counts for more as the board fills up and a tiebreak becomes likely.
+    fn evaluate_majority(&self, board: &Board, ai_player: Marker) -> i32 {
+        let mut lead = 0;
+        let mut decided = 0;
+        for b in &board.boards {
+            match b.status {
+                Status::Won(winner) if winner == ai_player => lead += 1,
+                Status::Won(_) => lead -= 1,
+                _ => {}
+            }
+            if !matches!(b.status, Status::InProgress) {
+                decided += 1;
+            }
+        }
+        lead * self.weights.board_majority * (1 + decided) / board.boards.len() as i32
+    }
+
+    fn evaluate_next_board(
+        &self,
+        board: &Board,
+        rules: RuleSet,
+        next_board: usize,
+        opponent: Marker,
+        micro_sign: i32,
+    ) -> i32 {
+        if next_board >= board.boards.len() || !rules.is_playable(&board.boards[next_board]) {
             return 100;
         }
+        // A won board that is still playable gives the opponent nothing to gain.
+        if !matches!(board.boards[next_board].status, Status::InProgress) {
+            return 0;
+        }
 
-        let threat = self.evaluate_micro_board(&board.boards[next_board], opponent);
+        let threat = micro_sign
+            * self.evaluate_micro_board(&board.boards[next_board], rules.board_size, opponent);
         -threat / self.weights.next_board_penalty
     }
 
-    fn evaluate_micro_board(&self, board: &MacroBoard, ai_player: Marker) -> i32 {
+    fn evaluate_micro_board(&self, board: &MacroBoard, size: BoardSize, ai_player: Marker) -> i32 {
         let opponent = !ai_player;
         let mut score = 0;
         let w = &self.weights;
+        let critical = size.side() as i32 - 1;
 
-        for line in &WINNING_LINES {
+        for line in size.lines() {
             let (ai_count, opp_count, empty_count) = self.count_micro_line(board, line, ai_player);
 
             if opp_count == 0 && ai_count > 0 {
                 score += match (ai_count, empty_count) {
-                    (2, 1) => w.micro_two_in_row,
-                    (1, 2) => w.micro_one_in_row,
+                    (count, 1) if count == critical => w.micro_two_in_row,
+                    (count, _) if count < critical => w.micro_one_in_row,
                     _ => 0,
                 };
             }
 
             if ai_count == 0 && opp_count > 0 {
                 score -= match (opp_count, empty_count) {
-                    (2, 1) => w.micro_two_in_row,
-                    (1, 2) => w.micro_one_in_row,
+                    (count, 1) if count == critical => w.micro_two_in_row,
+                    (count, _) if count < critical => w.micro_one_in_row,
                     _ => 0,
                 };
             }
         }
 
-        match board.cells[4] {
-            cell if cell == ai_player => score += w.micro_center,
-            cell if cell == opponent => score -= w.micro_center,
-            _ => {}
+        for (i, &cell) in board.cells.iter().enumerate() {
+            if !size.is_center(i) {
+                continue;
+            }
+            match cell {
+                cell if cell == ai_player => score += w.micro_center,
+                cell if cell == opponent => score -= w.micro_center,
+                _ => {}
+            }
         }
 
-        for &corner in &[0, 2, 6, 8] {
+        for corner in size.corners() {
             match board.cells[corner] {
                 cell if cell == ai_player => score += w.micro_corner,
                 cell if cell == opponent => score -= w.micro_corner,
@@ -422,11 +499,13 @@ impl StateEvaluator {
     fn count_line_status(
         &self,
         board: &Board,
-        line: &[usize; 3],
+        rules: RuleSet,
+        line: &[usize],
         ai_player: Marker,
     ) -> (i32, i32, i32) {
         let mut ai_won = 0;
         let mut opp_won = 0;
+        let mut drawn = 0;
         let mut available = 0;
 
         for &pos in line {
@@ -434,6 +513,17 @@ impl StateEvaluator {
                 Status::Won(winner) if winner == ai_player => ai_won += 1,
                 Status::Won(_) => opp_won += 1,
                 Status::InProgress => available += 1,
+                Status::Draw => drawn += 1,
+                _ => {}
+            }
+        }
+
+        // Shared boards only help a side the line is still open to.
+        if rules.drawn_boards_count_for_both {
+            match (ai_won, opp_won) {
+                (0, 0) => {}
+                (_, 0) => ai_won += drawn,
+                (0, _) => opp_won += drawn,
                 _ => {}
             }
         }
@@ -444,7 +534,7 @@ impl StateEvaluator {
     fn count_micro_line(
         &self,
         board: &MacroBoard,
-        line: &[usize; 3],
+        line: &[usize],
         ai_player: Marker,
     ) -> (i32, i32, i32) {
         let opponent = !ai_player;
@@ -469,18 +559,18 @@ pub struct MoveGenerator;
 
 impl MoveGenerator {
     pub fn generate_moves(state: &GameState) -> Vec<Move> {
-        let mut moves = Vec::with_capacity(MAX_CELLS);
+        let mut moves = Vec::with_capacity(state.rules.board_size.cells());
 
         if let Some(board_idx) = state.next_board {
-            if board_idx < MAX_BOARDS
-                && matches!(state.board.boards[board_idx].status, Status::InProgress)
+            if board_idx < state.board.boards.len()
+                && state.rules.is_playable(&state.board.boards[board_idx])
             {
                 Self::add_board_moves(board_idx, &state.board.boards[board_idx], &mut moves);
             } else {
-                Self::add_all_moves(&state.board, &mut moves);
+                Self::add_all_moves(&state.board, state.rules, &mut moves);
             }
         } else {
-            Self::add_all_moves(&state.board, &mut moves);
+            Self::add_all_moves(&state.board, state.rules, &mut moves);
         }
 
         moves
@@ -488,8 +578,8 @@ impl MoveGenerator {
 
     pub fn count_moves(state: &GameState) -> usize {
         if let Some(board_idx) = state.next_board
-            && board_idx < MAX_BOARDS
-            && matches!(state.board.boards[board_idx].status, Status::InProgress)
+            && board_idx < state.board.boards.len()
+            && state.rules.is_playable(&state.board.boards[board_idx])
         {
             return state.board.boards[board_idx]
                 .cells
@@ -502,7 +592,7 @@ impl MoveGenerator {
             .board
             .boards
             .iter()
-            .filter(|b| matches!(b.status, Status::InProgress))
+            .filter(|b| state.rules.is_playable(b))
             .map(|b| {
                 b.cells
                     .iter()
@@ -553,16 +643,16 @@ impl MoveGenerator {
     }
 
     fn add_board_moves(board_idx: usize, board: &MacroBoard, moves: &mut Vec<Move>) {
-        for cell_idx in 0..MAX_CELLS {
+        for cell_idx in 0..board.cells.len() {
             if matches!(board.cells[cell_idx], Marker::Empty) {
                 moves.push(Move::new(board_idx, cell_idx));
             }
         }
     }
 
-    fn add_all_moves(board: &Board, moves: &mut Vec<Move>) {
-        for board_idx in 0..MAX_BOARDS {
-            if matches!(board.boards[board_idx].status, Status::InProgress) {
+    fn add_all_moves(board: &Board, rules: RuleSet, moves: &mut Vec<Move>) {
+        for board_idx in 0..board.boards.len() {
+            if rules.is_playable(&board.boards[board_idx]) {
                 Self::add_board_moves(board_idx, &board.boards[board_idx], moves);
             }
         }
@@ -573,36 +663,34 @@ pub struct GameStateManager;
 
 impl GameStateManager {
     pub fn apply_move(state: &mut GameState, mov: Move, player: Marker) {
-        state.board.boards[mov.board_index].cells[mov.cell_index] = player;
-        state.board.boards[mov.board_index].status =
-            Self::check_board_status(&state.board.boards[mov.board_index]);
+        let board = &mut state.board.boards[mov.board_index];
+        board.cells[mov.cell_index] = player;
+        // Under `won_boards_playable` a won board keeps its first winner.
+        if matches!(board.status, Status::InProgress) {
+            board.status = Self::check_board_status(board, state.rules);
+        }
 
         state.last_move = Some([mov.board_index, mov.cell_index]);
 
-        state.next_board = if matches!(
-            state.board.boards[mov.cell_index].status,
-            Status::InProgress
-        ) {
+        state.next_board = if state.rules.is_playable(&state.board.boards[mov.cell_index]) {
             Some(mov.cell_index)
         } else {
             None
         };
 
         state.toggle_players();
-        state.board.status = Self::check_overall_status(&state.board);
+        state.board.status = Self::check_overall_status(&state.board, state.rules, player);
     }
 
     pub fn is_terminal(state: &GameState) -> bool {
         !matches!(state.board.status, Status::InProgress)
     }
 
-    fn check_board_status(board: &MacroBoard) -> Status {
-        for line in &WINNING_LINES {
-            if !matches!(board.cells[line[0]], Marker::Empty)
-                && board.cells[line[0]] == board.cells[line[1]]
-                && board.cells[line[1]] == board.cells[line[2]]
-            {
-                return Status::Won(board.cells[line[0]]);
+    fn check_board_status(board: &MacroBoard, rules: RuleSet) -> Status {
+        for line in rules.board_size.lines() {
+            let first = board.cells[line[0]];
+            if !matches!(first, Marker::Empty) && line.iter().all(|&i| board.cells[i] == first) {
+                return Status::Won(rules.sub_board_winner(first));
             }
         }
 
@@ -617,25 +705,20 @@ impl GameStateManager {
         Status::InProgress
     }
 
-    fn check_overall_status(board: &Board) -> Status {
-        for line in &WINNING_LINES {
-            if let (Status::Won(a), Status::Won(b), Status::Won(c)) = (
-                &board.boards[line[0]].status,
-                &board.boards[line[1]].status,
-                &board.boards[line[2]].status,
-            ) && a == b
-                && b == c
+    fn check_overall_status(board: &Board, rules: RuleSet, mover: Marker) -> Status {
+        for marker in [mover, !mover] {
+            if rules
+                .board_size
+                .lines()
+                .iter()
+                .any(|line| rules.completes_line(board, line, marker))
             {
-                return Status::Won(*a);
+                return Status::Won(rules.game_winner(marker));
             }
         }
 
-        if board
-            .boards
-            .iter()
-            .all(|b| !matches!(b.status, Status::InProgress))
-        {
-            return Status::Draw;
+        if board.boards.iter().all(|b| !rules.is_playable(b)) {
+            return rules.exhausted_status(board);
         }
 
         Status::InProgress
@@ -647,6 +730,7 @@ pub struct MinimaxAI {
     evaluator: StateEvaluator,
     killer_moves: KillerMoves,
     nodes_searched: usize,
+    last_evaluation: Option<i32>,
 }
 
 impl MinimaxAI {
@@ -660,6 +744,7 @@ impl MinimaxAI {
             evaluator,
             killer_moves: KillerMoves::new(),
             nodes_searched: 0,
+            last_evaluation: None,
         })
     }
 
@@ -670,6 +755,7 @@ impl MinimaxAI {
             evaluator,
             killer_moves: KillerMoves::new(),
             nodes_searched: 0,
+            last_evaluation: None,
         }
     }
 
@@ -677,15 +763,58 @@ impl MinimaxAI {
         self.nodes_searched
     }
 
+    /// The score of the best move found by the last `find_best_move`, from
+    /// the mover's side. `None` when that move was not searched.
+    pub fn last_evaluation(&self) -> Option<i32> {
+        self.last_evaluation
+    }
+
     pub fn find_best_move(&mut self, game_state: &GameState, player: Marker) -> Option<Move> {
-        let start_time = Instant::now();
         self.nodes_searched = 0;
+        self.last_evaluation = None;
 
         if self.should_make_random_move() {
             return self.find_random_move(game_state);
         }
 
-        let moves = if self.config.use_move_ordering {
+        let moves = self.root_moves(game_state, player);
+        if moves.is_empty() {
+            return None;
+        }
+
+        if moves.len() == 1 {
+            return Some(moves[0]);
+        }
+
+        let move_scores = self.score_moves(game_state, player, &moves);
+        self.last_evaluation = Some(move_scores[0].1);
+
+        if self.should_make_mistake() {
+            self.select_mistake_move(&move_scores)
+        } else {
+            Some(move_scores[0].0)
+        }
+    }
+
+    /// Searches the position at full strength, without random moves or
+    /// mistakes, and scores it for `player` whoever is to move.
+    pub fn evaluate_position(&mut self, game_state: &GameState, player: Marker) -> i32 {
+        self.nodes_searched = 0;
+        let to_move = game_state
+            .players
+            .get(game_state.current_index)
+            .map_or(player, |p| p.marker);
+        let moves = self.root_moves(game_state, to_move);
+        let score = if moves.is_empty() {
+            self.evaluator.evaluate(game_state, to_move)
+        } else {
+            self.score_moves(game_state, to_move, &moves)[0].1
+        };
+        if to_move == player { score } else { -score }
+    }
+
+    fn root_moves(&mut self, game_state: &GameState, player: Marker) -> Vec<Move> {
+        if self.config.use_move_ordering {
             let killers = if self.config.use_killer_moves {
                 Some(&self.killer_moves)
             } else {
@@ -700,19 +829,20 @@ impl MinimaxAI {
             )
         } else {
             MoveGenerator::generate_moves(game_state)
-        };
-
-        if moves.is_empty() {
-            return None;
-        }
-
-        if moves.len() == 1 {
-            return Some(moves[0]);
         }
+    }
 
+    /// Best first.
+    fn score_moves(
+        &mut self,
+        game_state: &GameState,
+        player: Marker,
+        moves: &[Move],
+    ) -> Vec<(Move, i32)> {
+        let start_time = Instant::now();
         let mut move_scores: Vec<(Move, i32)> = Vec::new();
 
-        for mov in &moves {
+        for mov in moves {
             let mut new_state = game_state.clone();
             GameStateManager::apply_move(&mut new_state, *mov, player);
 
@@ -730,12 +860,7 @@ impl MinimaxAI {
         }
 
         move_scores.sort_by_key(|b| std::cmp::Reverse(b.1));
-
-        if self.should_make_mistake() {
-            self.select_mistake_move(&move_scores)
-        } else {
-            Some(move_scores[0].0)
-        }
+        move_scores
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -884,3 +1009,68 @@ impl MinimaxAI {
         Some(valid_moves[index])
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+    use ultimatexo_core::{
+        GameEngine,
+        models::{MatchFormat, PlayerInfo},
+    };
+
+    fn rule_sets() -> Vec<RuleSet> {
+        let mut rule_sets = Vec::new();
+        for board_size in [BoardSize::Three, BoardSize::Four] {
+            for flags in 0..32u8 {
+                rule_sets.push(RuleSet {
+                    won_boards_playable: flags & 1 != 0,
+                    drawn_boards_count_for_both: flags & 2 != 0,
+                    majority_tiebreak: flags & 4 != 0,
+                    misere: flags & 8 != 0,
+                    misere_sub_boards: flags & 16 != 0,
+                    board_size,
+                });
+            }
+        }
+        rule_sets
+    }
+
+    /// The search applies moves with `GameStateManager`; it must reach the
+    /// same results as the engine that referees real games.
+    #[test]
+    fn search_rules_agree_with_game_engine() {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        for rules in rule_sets() {
+            for game in 0..8 {
+                let mut engine = GameEngine::new(None, rules, MatchFormat::Open, None);
+                engine.push_player(PlayerInfo::new(Marker::X));
+                engine.push_player(PlayerInfo::new(Marker::O));
+                engine.set_board_status(Status::InProgress);
+                let mut state = engine.state.clone();
+
+                while engine.get_board_status() == Status::InProgress {
+                    let moves = MoveGenerator::generate_moves(&state);
+                    assert!(!moves.is_empty(), "{rules:?} game {game}: no moves");
+                    let mov = moves[rng.random_range(0..moves.len())];
+                    let mover = engine.get_current_player().marker;
+
+                    engine
+                        .make_move([mov.board_index, mov.cell_index])
+                        .unwrap_or_else(|e| panic!("{rules:?} game {game}: {mov:?} {e}"));
+                    GameStateManager::apply_move(&mut state, mov, mover);
+
+                    let context = format!("{rules:?} game {game} after {mov:?}");
+                    assert_eq!(state.board.status, engine.state.board.status, "{context}");
+                    assert_eq!(state.next_board, engine.state.next_board, "{context}");
+                    for (searched, refereed) in
+                        state.board.boards.iter().zip(&engine.state.board.boards)
+                    {
+                        assert_eq!(searched.status, refereed.status, "{context}");
+                    }
+                }
+                assert!(GameStateManager::is_terminal(&state));
+            }
+        }
+    }
+}
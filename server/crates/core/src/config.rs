@@ -0,0 +1,883 @@
+use crate::models::BotLevel;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap, env, fmt::Display, fs, io, net::SocketAddr, path::Path, str::FromStr,
+    time::Duration,
+};
+use thiserror::Error;
+
+/// Looked up in the working directory when no `--config` path is given.
+pub const DEFAULT_CONFIG_PATH: &str = "ultimatexo.toml";
+const REDACTED: &str = "<redacted>";
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("failed to read config file {path}: {source}")]
+    Read { path: String, source: io::Error },
+    #[error("invalid config file {path}: {message}")]
+    Parse { path: String, message: String },
+    #[error("{var} has invalid value {value:?}: {message}")]
+    Env {
+        var: &'static str,
+        value: String,
+        message: String,
+    },
+    #[error("invalid configuration:\n  - {}", .0.join("\n  - "))]
+    Invalid(Vec<String>),
+}
+
+/// Every tunable the server reads, loaded once at startup and passed down to
+/// the services and rules that need it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub server: ListenSettings,
+    pub bots: BotSettings,
+    pub bot_levels: BotLevelSettings,
+    pub rooms: RoomSettings,
+    pub websocket: WebSocketSettings,
+    pub rate_limit: RateLimitSettings,
+    pub privacy: PrivacySettings,
+    pub metrics: MetricsSettings,
+    pub admin: AdminSettings,
+    pub shutdown: ShutdownSettings,
+    pub webhooks: WebhookSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ListenSettings {
+    pub host: String,
+    pub port: u16,
+}
+
+impl Default for ListenSettings {
+    fn default() -> Self {
+        Self {
+            host: "localhost".into(),
+            port: 6767,
+        }
+    }
+}
+
+/// Search difficulty (1–10) used for each `BotLevel`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BotSettings {
+    pub beginner_difficulty: u8,
+    pub medium_difficulty: u8,
+    pub hard_difficulty: u8,
+    pub expert_difficulty: u8,
+    pub behaviour: BotBehaviourSettings,
+    pub limits: BotLimits,
+}
+
+impl Default for BotSettings {
+    fn default() -> Self {
+        Self {
+            beginner_difficulty: 1,
+            medium_difficulty: 4,
+            hard_difficulty: 7,
+            expert_difficulty: 10,
+            behaviour: BotBehaviourSettings::default(),
+            limits: BotLimits::default(),
+        }
+    }
+}
+
+impl BotSettings {
+    pub fn difficulty_for(&self, level: &BotLevel) -> u8 {
+        match level {
+            BotLevel::Beginner => self.beginner_difficulty,
+            BotLevel::Medium => self.medium_difficulty,
+            BotLevel::Hard => self.hard_difficulty,
+            BotLevel::Expert => self.expert_difficulty,
+        }
+    }
+
+    /// The level whose difficulty is nearest, the harder one on a tie.
+    pub fn level_for(&self, difficulty: u8) -> BotLevel {
+        [
+            BotLevel::Expert,
+            BotLevel::Hard,
+            BotLevel::Medium,
+            BotLevel::Beginner,
+        ]
+        .into_iter()
+        .min_by_key(|level| self.difficulty_for(level).abs_diff(difficulty))
+        .unwrap_or(BotLevel::Medium)
+    }
+}
+
+/// Ceilings for the bots a room creator can configure with `BotConfig`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BotLimits {
+    pub max_level: u8,
+    pub max_depth: usize,
+    pub max_time_ms: u64,
+}
+
+impl Default for BotLimits {
+    fn default() -> Self {
+        Self {
+            max_level: 10,
+            max_depth: 10,
+            max_time_ms: 5_000,
+        }
+    }
+}
+
+/// How each `BotLevel` answers draw offers, lost positions and rematches.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BotBehaviourSettings {
+    pub beginner: BotBehaviour,
+    pub medium: BotBehaviour,
+    pub hard: BotBehaviour,
+    pub expert: BotBehaviour,
+}
+
+impl Default for BotBehaviourSettings {
+    fn default() -> Self {
+        Self {
+            beginner: BotBehaviour {
+                accept_draw_below: 1000,
+                resign_below: None,
+                rematch_delay_ms: 600,
+                max_adaptive_difficulty: 5,
+            },
+            medium: BotBehaviour {
+                accept_draw_below: 0,
+                resign_below: Some(-20_000),
+                rematch_delay_ms: 900,
+                max_adaptive_difficulty: 7,
+            },
+            hard: BotBehaviour {
+                accept_draw_below: -500,
+                resign_below: Some(-50_000),
+                rematch_delay_ms: 1200,
+                max_adaptive_difficulty: 8,
+            },
+            expert: BotBehaviour {
+                accept_draw_below: -2000,
+                resign_below: Some(-90_000),
+                rematch_delay_ms: 1500,
+                max_adaptive_difficulty: 10,
+            },
+        }
+    }
+}
+
+impl BotBehaviourSettings {
+    pub fn get(&self, level: &BotLevel) -> BotBehaviour {
+        match level {
+            BotLevel::Beginner => self.beginner,
+            BotLevel::Medium => self.medium,
+            BotLevel::Hard => self.hard,
+            BotLevel::Expert => self.expert,
+        }
+    }
+}
+
+/// Thresholds are engine scores from the bot's side, where a won game is
+/// 100000 and a lost one -100000.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BotBehaviour {
+    /// Draw offers are accepted when the bot's score is below this.
+    pub accept_draw_below: i32,
+    /// The bot resigns instead of moving once its score falls below this.
+    pub resign_below: Option<i32>,
+    /// How long the bot takes to accept a rematch.
+    pub rematch_delay_ms: u64,
+    /// The strongest an adaptive room at this level can get.
+    pub max_adaptive_difficulty: u8,
+}
+
+impl Default for BotBehaviour {
+    fn default() -> Self {
+        BotBehaviourSettings::default().medium
+    }
+}
+
+impl BotBehaviour {
+    pub fn rematch_delay(&self) -> Duration {
+        Duration::from_millis(self.rematch_delay_ms)
+    }
+}
+
+/// Which bot levels can be picked when creating a room. Changeable at
+/// runtime through the admin API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BotLevelSettings {
+    pub beginner: BotAvailability,
+    pub medium: BotAvailability,
+    pub hard: BotAvailability,
+    pub expert: BotAvailability,
+}
+
+impl Default for BotLevelSettings {
+    fn default() -> Self {
+        Self {
+            beginner: BotAvailability::default(),
+            medium: BotAvailability::default(),
+            hard: BotAvailability::default(),
+            expert: BotAvailability {
+                enabled: false,
+                message: Some("Expert bot is currently disabled.".into()),
+            },
+        }
+    }
+}
+
+impl BotLevelSettings {
+    pub fn get(&self, level: &BotLevel) -> &BotAvailability {
+        match level {
+            BotLevel::Beginner => &self.beginner,
+            BotLevel::Medium => &self.medium,
+            BotLevel::Hard => &self.hard,
+            BotLevel::Expert => &self.expert,
+        }
+    }
+
+    pub fn get_mut(&mut self, level: &BotLevel) -> &mut BotAvailability {
+        match level {
+            BotLevel::Beginner => &mut self.beginner,
+            BotLevel::Medium => &mut self.medium,
+            BotLevel::Hard => &mut self.hard,
+            BotLevel::Expert => &mut self.expert,
+        }
+    }
+
+    /// The message shown to the client when `level` is disabled.
+    pub fn rejection(&self, level: &BotLevel) -> Option<String> {
+        let availability = self.get(level);
+        if availability.enabled {
+            return None;
+        }
+        Some(
+            availability
+                .message
+                .clone()
+                .unwrap_or_else(|| format!("{level:?} bot is currently disabled.")),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BotAvailability {
+    pub enabled: bool,
+    /// Shown instead of the generic message when the level is disabled.
+    pub message: Option<String>,
+}
+
+impl Default for BotAvailability {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            message: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RoomSettings {
+    /// How long a disconnected player in a standard room has to come back.
+    pub cleanup_timeout_secs: u64,
+}
+
+impl Default for RoomSettings {
+    fn default() -> Self {
+        Self {
+            cleanup_timeout_secs: 60,
+        }
+    }
+}
+
+impl RoomSettings {
+    pub fn cleanup_timeout(&self) -> Duration {
+        Duration::from_secs(self.cleanup_timeout_secs)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebSocketSettings {
+    pub ping_interval_secs: u64,
+    pub pong_timeout_secs: u64,
+}
+
+impl Default for WebSocketSettings {
+    fn default() -> Self {
+        Self {
+            ping_interval_secs: 3,
+            pong_timeout_secs: 10,
+        }
+    }
+}
+
+impl WebSocketSettings {
+    pub fn ping_interval(&self) -> Duration {
+        Duration::from_secs(self.ping_interval_secs)
+    }
+
+    pub fn pong_timeout(&self) -> Duration {
+        Duration::from_secs(self.pong_timeout_secs)
+    }
+}
+
+/// Per-client request limits. Routes without an entry in `routes` share the
+/// `default` bucket. Everything but `cleanup_interval_secs` can be changed at
+/// runtime through the admin API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitSettings {
+    /// Off by default in debug builds so local development is not throttled.
+    pub enabled: bool,
+    pub cleanup_interval_secs: u64,
+    pub default: RateLimitPolicy,
+    pub routes: BTreeMap<RateLimitRoute, RateLimitPolicy>,
+}
+
+impl Default for RateLimitSettings {
+    fn default() -> Self {
+        Self {
+            enabled: !cfg!(debug_assertions),
+            cleanup_interval_secs: 60,
+            default: RateLimitPolicy::default(),
+            routes: BTreeMap::new(),
+        }
+    }
+}
+
+impl RateLimitSettings {
+    pub fn policy_for(&self, route: RateLimitRoute) -> Option<RateLimitPolicy> {
+        self.routes.get(&route).copied()
+    }
+
+    fn problems(&self, problems: &mut Vec<String>) {
+        if self.cleanup_interval_secs == 0 {
+            problems.push("rate_limit.cleanup_interval_secs must be at least 1".to_string());
+        }
+        self.default.problems("rate_limit.default", problems);
+        for (route, policy) in &self.routes {
+            policy.problems(&format!("rate_limit.routes.{}", route.as_str()), problems);
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+        self.problems(&mut problems);
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+}
+
+/// Requests are replenished at `per_second`, with up to `burst` allowed at
+/// once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitPolicy {
+    pub per_second: u32,
+    pub burst: u32,
+}
+
+impl Default for RateLimitPolicy {
+    fn default() -> Self {
+        Self {
+            per_second: 2,
+            burst: 5,
+        }
+    }
+}
+
+impl RateLimitPolicy {
+    fn problems(&self, name: &str, problems: &mut Vec<String>) {
+        if self.per_second == 0 {
+            problems.push(format!("{name}.per_second must be at least 1"));
+        }
+        if self.burst == 0 {
+            problems.push(format!("{name}.burst must be at least 1"));
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitRoute {
+    ListRooms,
+    CreateRoom,
+    GetRoom,
+    ClientError,
+    Health,
+    Lobby,
+    Game,
+    Admin,
+}
+
+impl RateLimitRoute {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RateLimitRoute::ListRooms => "list_rooms",
+            RateLimitRoute::CreateRoom => "create_room",
+            RateLimitRoute::GetRoom => "get_room",
+            RateLimitRoute::ClientError => "client_error",
+            RateLimitRoute::Health => "health",
+            RateLimitRoute::Lobby => "lobby",
+            RateLimitRoute::Game => "game",
+            RateLimitRoute::Admin => "admin",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PrivacySettings {
+    /// Salt for client IP hashes; a daily rotating salt is used when unset.
+    pub ip_hash_salt: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MetricsSettings {
+    pub bind: Option<String>,
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AdminSettings {
+    /// Bearer token for `/admin`; the admin API is not mounted when unset.
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShutdownSettings {
+    /// How long games in progress get to finish once shutdown starts.
+    pub grace_period_secs: u64,
+    pub warning_interval_secs: u64,
+    /// How long event subscribers such as webhooks get to deliver the final
+    /// snapshots.
+    pub flush_timeout_secs: u64,
+}
+
+impl Default for ShutdownSettings {
+    fn default() -> Self {
+        Self {
+            grace_period_secs: 30,
+            warning_interval_secs: 10,
+            flush_timeout_secs: 10,
+        }
+    }
+}
+
+impl ShutdownSettings {
+    pub fn flush_timeout(&self) -> Duration {
+        Duration::from_secs(self.flush_timeout_secs)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookSettings {
+    pub urls: Vec<String>,
+    pub secret: Option<String>,
+    /// Event names to deliver; every event when unset.
+    pub events: Option<Vec<String>>,
+    pub max_attempts: u32,
+    pub timeout_secs: u64,
+}
+
+impl Default for WebhookSettings {
+    fn default() -> Self {
+        Self {
+            urls: Vec::new(),
+            secret: None,
+            events: None,
+            max_attempts: 5,
+            timeout_secs: 10,
+        }
+    }
+}
+
+impl ServerConfig {
+    /// Reads `path` (or `ultimatexo.toml` if present), applies environment
+    /// overrides and validates the result.
+    pub fn load(path: Option<&Path>) -> Result<Self, ConfigError> {
+        let mut config = match path {
+            Some(path) => Self::from_file(path)?,
+            None if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_PATH))?
+            }
+            None => Self::default(),
+        };
+        config.apply_env()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn from_file(path: &Path) -> Result<Self, ConfigError> {
+        let raw = fs::read_to_string(path).map_err(|source| ConfigError::Read {
+            path: path.display().to_string(),
+            source,
+        })?;
+        toml::from_str(&raw).map_err(|e| ConfigError::Parse {
+            path: path.display().to_string(),
+            message: e.to_string(),
+        })
+    }
+
+    fn apply_env(&mut self) -> Result<(), ConfigError> {
+        self.apply_overrides(Overrides(|var: &str| env::var(var).ok()))
+    }
+
+    fn apply_overrides<F>(&mut self, env: Overrides<F>) -> Result<(), ConfigError>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        env.string(&mut self.server.host, "HOST");
+        env.parsed(&mut self.server.port, "PORT")?;
+        env.parsed(
+            &mut self.bots.beginner_difficulty,
+            "BOT_BEGINNER_DIFFICULTY",
+        )?;
+        env.parsed(&mut self.bots.medium_difficulty, "BOT_MEDIUM_DIFFICULTY")?;
+        env.parsed(&mut self.bots.hard_difficulty, "BOT_HARD_DIFFICULTY")?;
+        env.parsed(&mut self.bots.expert_difficulty, "BOT_EXPERT_DIFFICULTY")?;
+        env.parsed(
+            &mut self.bot_levels.beginner.enabled,
+            "BOT_BEGINNER_ENABLED",
+        )?;
+        env.parsed(&mut self.bot_levels.medium.enabled, "BOT_MEDIUM_ENABLED")?;
+        env.parsed(&mut self.bot_levels.hard.enabled, "BOT_HARD_ENABLED")?;
+        env.parsed(&mut self.bot_levels.expert.enabled, "BOT_EXPERT_ENABLED")?;
+        env.parsed(
+            &mut self.rooms.cleanup_timeout_secs,
+            "ROOM_CLEANUP_TIMEOUT_SECS",
+        )?;
+        env.parsed(
+            &mut self.websocket.ping_interval_secs,
+            "WEBSOCKET_PING_INTERVAL_SECS",
+        )?;
+        env.parsed(
+            &mut self.websocket.pong_timeout_secs,
+            "WEBSOCKET_PONG_TIMEOUT_SECS",
+        )?;
+        env.parsed(
+            &mut self.rate_limit.cleanup_interval_secs,
+            "GOVERNOR_CLEANUP_INTERVAL_SECS",
+        )?;
+        env.parsed(&mut self.rate_limit.enabled, "RATE_LIMIT_ENABLED")?;
+        env.parsed(
+            &mut self.rate_limit.default.per_second,
+            "RATE_LIMIT_PER_SECOND",
+        )?;
+        env.parsed(&mut self.rate_limit.default.burst, "RATE_LIMIT_BURST")?;
+        env.optional(&mut self.privacy.ip_hash_salt, "IP_HASH_SALT");
+        env.optional(&mut self.metrics.bind, "METRICS_BIND");
+        env.optional(&mut self.metrics.token, "METRICS_TOKEN");
+        env.optional(&mut self.admin.token, "ADMIN_TOKEN");
+        env.parsed(
+            &mut self.shutdown.grace_period_secs,
+            "SHUTDOWN_GRACE_PERIOD_SECS",
+        )?;
+        env.parsed(
+            &mut self.shutdown.warning_interval_secs,
+            "SHUTDOWN_WARNING_INTERVAL_SECS",
+        )?;
+        env.parsed(
+            &mut self.shutdown.flush_timeout_secs,
+            "SHUTDOWN_FLUSH_TIMEOUT_SECS",
+        )?;
+        if let Some(urls) = env.value("WEBHOOK_URLS") {
+            self.webhooks.urls = split_list(&urls);
+        }
+        env.optional(&mut self.webhooks.secret, "WEBHOOK_SECRET");
+        if let Some(events) = env.value("WEBHOOK_EVENTS") {
+            self.webhooks.events = Some(split_list(&events));
+        }
+        env.parsed(&mut self.webhooks.max_attempts, "WEBHOOK_MAX_ATTEMPTS")?;
+        env.parsed(&mut self.webhooks.timeout_secs, "WEBHOOK_TIMEOUT_SECS")?;
+        Ok(())
+    }
+
+    /// Collects every problem instead of stopping at the first one.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut problems = Vec::new();
+
+        if self.server.host.trim().is_empty() {
+            problems.push("server.host must not be empty".to_string());
+        }
+        for (name, difficulty) in [
+            ("bots.beginner_difficulty", self.bots.beginner_difficulty),
+            ("bots.medium_difficulty", self.bots.medium_difficulty),
+            ("bots.hard_difficulty", self.bots.hard_difficulty),
+            ("bots.expert_difficulty", self.bots.expert_difficulty),
+        ] {
+            if !(1..=10).contains(&difficulty) {
+                problems.push(format!("{name} must be between 1 and 10, got {difficulty}"));
+            }
+        }
+        for (name, behaviour) in [
+            ("bots.behaviour.beginner", self.bots.behaviour.beginner),
+            ("bots.behaviour.medium", self.bots.behaviour.medium),
+            ("bots.behaviour.hard", self.bots.behaviour.hard),
+            ("bots.behaviour.expert", self.bots.behaviour.expert),
+        ] {
+            if behaviour.rematch_delay_ms > 10_000 {
+                problems.push(format!(
+                    "{name}.rematch_delay_ms must be at most 10000, got {}",
+                    behaviour.rematch_delay_ms
+                ));
+            }
+            if !(1..=10).contains(&behaviour.max_adaptive_difficulty) {
+                problems.push(format!(
+                    "{name}.max_adaptive_difficulty must be between 1 and 10, got {}",
+                    behaviour.max_adaptive_difficulty
+                ));
+            }
+        }
+        if !(1..=10).contains(&self.bots.limits.max_level) {
+            problems.push(format!(
+                "bots.limits.max_level must be between 1 and 10, got {}",
+                self.bots.limits.max_level
+            ));
+        }
+        if self.bots.limits.max_depth == 0 {
+            problems.push("bots.limits.max_depth must be at least 1".to_string());
+        }
+        if self.bots.limits.max_time_ms == 0 {
+            problems.push("bots.limits.max_time_ms must be at least 1".to_string());
+        }
+        if self.rooms.cleanup_timeout_secs == 0 {
+            problems.push("rooms.cleanup_timeout_secs must be at least 1".to_string());
+        }
+        if self.websocket.ping_interval_secs == 0 {
+            problems.push("websocket.ping_interval_secs must be at least 1".to_string());
+        }
+        if self.websocket.pong_timeout_secs <= self.websocket.ping_interval_secs {
+            problems.push(format!(
+                "websocket.pong_timeout_secs ({}) must be greater than websocket.ping_interval_secs ({})",
+                self.websocket.pong_timeout_secs, self.websocket.ping_interval_secs
+            ));
+        }
+        self.rate_limit.problems(&mut problems);
+        if let Some(bind) = &self.metrics.bind
+            && bind.parse::<SocketAddr>().is_err()
+        {
+            problems.push(format!(
+                "metrics.bind must be an ip:port address, got {bind:?}"
+            ));
+        }
+        if self.shutdown.warning_interval_secs == 0 {
+            problems.push("shutdown.warning_interval_secs must be at least 1".to_string());
+        }
+        if !self.webhooks.urls.is_empty() && self.webhooks.secret.is_none() {
+            problems.push("webhooks.secret is required when webhooks.urls is set".to_string());
+        }
+        for url in &self.webhooks.urls {
+            if !(url.starts_with("http://") || url.starts_with("https://")) {
+                problems.push(format!(
+                    "webhooks.urls entry {url:?} must be an http(s) URL"
+                ));
+            }
+        }
+        if self.webhooks.max_attempts == 0 {
+            problems.push("webhooks.max_attempts must be at least 1".to_string());
+        }
+        if self.webhooks.timeout_secs == 0 {
+            problems.push("webhooks.timeout_secs must be at least 1".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(problems))
+        }
+    }
+
+    /// The effective configuration as TOML, with secrets masked.
+    pub fn to_redacted_toml(&self) -> String {
+        let mut config = self.clone();
+        for secret in [
+            &mut config.privacy.ip_hash_salt,
+            &mut config.metrics.token,
+            &mut config.admin.token,
+            &mut config.webhooks.secret,
+        ] {
+            if secret.is_some() {
+                *secret = Some(REDACTED.to_string());
+            }
+        }
+        toml::to_string_pretty(&config).unwrap_or_default()
+    }
+}
+
+/// Environment overrides, read through a lookup so tests need not touch
+/// the process environment.
+struct Overrides<F>(F);
+
+impl<F> Overrides<F>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    /// Empty variables (as left behind by compose defaults) count as unset.
+    fn value(&self, var: &str) -> Option<String> {
+        (self.0)(var).filter(|value| !value.trim().is_empty())
+    }
+
+    fn string(&self, target: &mut String, var: &'static str) {
+        if let Some(value) = self.value(var) {
+            *target = value;
+        }
+    }
+
+    fn optional(&self, target: &mut Option<String>, var: &'static str) {
+        if let Some(value) = self.value(var) {
+            *target = Some(value);
+        }
+    }
+
+    fn parsed<T>(&self, target: &mut T, var: &'static str) -> Result<(), ConfigError>
+    where
+        T: FromStr,
+        T::Err: Display,
+    {
+        if let Some(value) = self.value(var) {
+            *target = value.trim().parse().map_err(|e: T::Err| ConfigError::Env {
+                var,
+                message: e.to_string(),
+                value,
+            })?;
+        }
+        Ok(())
+    }
+}
+
+fn split_list(raw: &str) -> Vec<String> {
+    raw.split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn overrides(vars: &[(&str, &str)]) -> Overrides<impl Fn(&str) -> Option<String>> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(var, value)| (var.to_string(), value.to_string()))
+            .collect();
+        Overrides(move |var: &str| vars.get(var).cloned())
+    }
+
+    #[test]
+    fn env_overrides_beat_the_file() {
+        let mut config: ServerConfig = toml::from_str(
+            "[server]\nhost = \"file-host\"\nport = 7000\n\n[webhooks]\nmax_attempts = 2\n",
+        )
+        .unwrap();
+        config
+            .apply_overrides(overrides(&[
+                ("PORT", "8000"),
+                ("ADMIN_TOKEN", "from-env"),
+                ("WEBHOOK_MAX_ATTEMPTS", " "),
+            ]))
+            .unwrap();
+
+        assert_eq!(config.server.port, 8000);
+        assert_eq!(config.admin.token.as_deref(), Some("from-env"));
+        // Untouched by the environment, or set to an empty value.
+        assert_eq!(config.server.host, "file-host");
+        assert_eq!(config.webhooks.max_attempts, 2);
+    }
+
+    #[test]
+    fn malformed_env_values_are_reported() {
+        for (var, value) in [("PORT", "eighty"), ("RATE_LIMIT_ENABLED", "yes")] {
+            let error = ServerConfig::default()
+                .apply_overrides(overrides(&[(var, value)]))
+                .unwrap_err();
+            match error {
+                ConfigError::Env {
+                    var: reported,
+                    value: reported_value,
+                    ..
+                } => {
+                    assert_eq!(reported, var);
+                    assert_eq!(reported_value, value);
+                }
+                other => panic!("expected an env error for {var}, got {other}"),
+            }
+        }
+    }
+
+    #[test]
+    fn validate_reports_every_problem_together() {
+        assert!(ServerConfig::default().validate().is_ok());
+
+        let mut config = ServerConfig::default();
+        config.server.host = " ".to_string();
+        config.bots.hard_difficulty = 11;
+        config.websocket.pong_timeout_secs = config.websocket.ping_interval_secs;
+        config.webhooks.urls = vec!["ftp://example.com/hook".to_string()];
+
+        let Err(ConfigError::Invalid(problems)) = config.validate() else {
+            panic!("expected the configuration to be rejected");
+        };
+        for expected in [
+            "server.host",
+            "bots.hard_difficulty",
+            "websocket.pong_timeout_secs",
+            "webhooks.secret",
+            "ftp://example.com/hook",
+        ] {
+            assert!(
+                problems.iter().any(|problem| problem.contains(expected)),
+                "no problem mentions {expected}: {problems:?}"
+            );
+        }
+        assert_eq!(problems.len(), 5, "{problems:?}");
+    }
+
+    #[test]
+    fn redacted_toml_masks_secrets() {
+        let mut config = ServerConfig::default();
+        config.admin.token = Some("admin-secret".to_string());
+        config.webhooks.secret = Some("webhook-secret".to_string());
+        config.metrics.token = Some("metrics-secret".to_string());
+        config.privacy.ip_hash_salt = Some("salt-secret".to_string());
+
+        let text = config.to_redacted_toml();
+        for secret in [
+            "admin-secret",
+            "webhook-secret",
+            "metrics-secret",
+            "salt-secret",
+        ] {
+            assert!(!text.contains(secret), "{secret} leaked:\n{text}");
+        }
+
+        let redacted: ServerConfig = toml::from_str(&text).unwrap();
+        assert_eq!(redacted.admin.token.as_deref(), Some(REDACTED));
+        assert_eq!(redacted.webhooks.secret.as_deref(), Some(REDACTED));
+        assert_eq!(redacted.server.port, config.server.port);
+        // Unset secrets stay unset rather than appearing redacted.
+        assert!(
+            !ServerConfig::default()
+                .to_redacted_toml()
+                .contains(REDACTED)
+        );
+    }
+}
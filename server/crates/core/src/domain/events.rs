@@ -0,0 +1,287 @@
+use crate::{
+    domain::RoomTransition,
+    models::{Action, Board, GameRecord, Marker, RoomInfo, Status},
+};
+use serde::Serialize;
+use tracing::{debug, info};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "data")]
+pub enum DomainEvent {
+    RoomCreated {
+        room: RoomInfo,
+    },
+    RoomTransitioned {
+        room_id: String,
+        transition: RoomTransition,
+    },
+    RoomRemoved {
+        room_id: String,
+    },
+    PlayerJoined {
+        room_id: String,
+        player_id: String,
+        marker: Marker,
+        client_hash: String,
+    },
+    PlayerLeft {
+        room_id: String,
+        player_id: String,
+        remaining_players: usize,
+    },
+    PlayerDisconnected {
+        room_id: String,
+        player_id: String,
+        timeout_secs: u64,
+    },
+    PlayerReconnected {
+        room_id: String,
+        player_id: String,
+    },
+    PlayerTimedOut {
+        room_id: String,
+        player_id: String,
+        result: Status,
+    },
+    MoveMade {
+        room_id: String,
+        /// `None` when the move was played by the bot.
+        player_id: Option<String>,
+        marker: Marker,
+        mv: [usize; 2],
+        board: Board,
+        next_board: Option<usize>,
+        next_player: Marker,
+    },
+    GameFinished {
+        record: GameRecord,
+    },
+    /// An unfinished game captured while the server shuts down.
+    GameInterrupted {
+        record: GameRecord,
+    },
+    PlayerResigned {
+        room_id: String,
+        player_id: String,
+        marker: Marker,
+    },
+    DrawOffer {
+        room_id: String,
+        player_id: String,
+        marker: Marker,
+        action: Action,
+    },
+    Rematch {
+        room_id: String,
+        player_id: String,
+        marker: Marker,
+        action: Action,
+    },
+    ChatMessage {
+        room_id: String,
+        player_id: String,
+        marker: Marker,
+        content: String,
+    },
+}
+
+impl DomainEvent {
+    pub fn room_id(&self) -> &str {
+        match self {
+            DomainEvent::RoomCreated { room } => &room.id,
+            DomainEvent::GameFinished { record } | DomainEvent::GameInterrupted { record } => {
+                &record.room_id
+            }
+            DomainEvent::RoomTransitioned { room_id, .. }
+            | DomainEvent::RoomRemoved { room_id }
+            | DomainEvent::PlayerJoined { room_id, .. }
+            | DomainEvent::PlayerLeft { room_id, .. }
+            | DomainEvent::PlayerDisconnected { room_id, .. }
+            | DomainEvent::PlayerReconnected { room_id, .. }
+            | DomainEvent::PlayerTimedOut { room_id, .. }
+            | DomainEvent::MoveMade { room_id, .. }
+            | DomainEvent::PlayerResigned { room_id, .. }
+            | DomainEvent::DrawOffer { room_id, .. }
+            | DomainEvent::Rematch { room_id, .. }
+            | DomainEvent::ChatMessage { room_id, .. } => room_id,
+        }
+    }
+
+    /// Emits the event's log line. Called where the event is published, so
+    /// the line keeps the publisher's span and never waits on the bus.
+    pub fn log(&self) {
+        match self {
+            DomainEvent::RoomCreated { room } => {
+                debug!(room_id = %room.id, room_type = ?room.room_type, "room_registered");
+            }
+            DomainEvent::RoomTransitioned {
+                room_id,
+                transition,
+            } => {
+                info!(
+                    room_id = %room_id,
+                    from = ?transition.from,
+                    to = ?transition.to,
+                    event = ?transition.event,
+                    "room_transition"
+                );
+            }
+            DomainEvent::RoomRemoved { room_id } => {
+                info!(room_id = %room_id, "room_removed");
+            }
+            DomainEvent::PlayerJoined {
+                room_id,
+                player_id,
+                marker,
+                client_hash,
+            } => {
+                info!(
+                    player_id = %player_id,
+                    room_id = %room_id,
+                    marker = ?marker,
+                    client_hash = %client_hash,
+                    "player_joined"
+                );
+            }
+            DomainEvent::PlayerLeft {
+                room_id,
+                player_id,
+                remaining_players,
+            } => {
+                info!(
+                    player_id = %player_id,
+                    room_id = %room_id,
+                    remaining_players = remaining_players,
+                    "player_leaving"
+                );
+            }
+            DomainEvent::PlayerDisconnected {
+                room_id,
+                player_id,
+                timeout_secs,
+            } => {
+                info!(
+                    player_id = %player_id,
+                    room_id = %room_id,
+                    timeout_seconds = timeout_secs,
+                    "player_disconnected"
+                );
+            }
+            DomainEvent::PlayerReconnected { room_id, player_id } => {
+                info!(player_id = %player_id, room_id = %room_id, "player_reconnected");
+            }
+            DomainEvent::PlayerTimedOut {
+                room_id,
+                player_id,
+                result,
+            } => {
+                info!(
+                    room_id = %room_id,
+                    player_id = %player_id,
+                    timeout_game_state = ?result,
+                    "game_timeout"
+                );
+            }
+            DomainEvent::MoveMade {
+                room_id,
+                player_id,
+                marker,
+                mv,
+                board,
+                next_board,
+                next_player,
+            } => {
+                let board_state = serde_json::to_string(board).unwrap_or_default();
+                match player_id {
+                    Some(player_id) => info!(
+                        player_id = %player_id,
+                        room_id = %room_id,
+                        marker = ?marker,
+                        move_board = mv[0],
+                        move_cell = mv[1],
+                        board_state = %board_state,
+                        active_board = ?next_board,
+                        next_player = ?next_player,
+                        game_status = ?board.status,
+                        "player_moved"
+                    ),
+                    None => info!(
+                        room_id = %room_id,
+                        marker = ?marker,
+                        move_board = mv[0],
+                        move_cell = mv[1],
+                        board_state = %board_state,
+                        active_board = ?next_board,
+                        next_player = ?next_player,
+                        game_status = ?board.status,
+                        "bot_moved"
+                    ),
+                }
+            }
+            DomainEvent::GameFinished { record } => {
+                info!(
+                    room_id = %record.room_id,
+                    room_type = ?record.room_type,
+                    result = ?record.result,
+                    moves = record.moves.len(),
+                    score = ?record.score,
+                    "game_finished"
+                );
+            }
+            DomainEvent::GameInterrupted { record } => {
+                info!(
+                    room_id = %record.room_id,
+                    room_type = ?record.room_type,
+                    moves = record.moves.len(),
+                    players = record.players.len(),
+                    "game_interrupted"
+                );
+            }
+            DomainEvent::PlayerResigned {
+                room_id,
+                player_id,
+                marker,
+            } => {
+                info!(
+                    player_id = %player_id,
+                    room_id = %room_id,
+                    marker = ?marker,
+                    "player_resigned"
+                );
+            }
+            DomainEvent::DrawOffer {
+                room_id,
+                player_id,
+                marker,
+                action,
+            } => {
+                info!(
+                    player_id = %player_id,
+                    room_id = %room_id,
+                    marker = ?marker,
+                    action = ?action,
+                    "draw_event"
+                );
+            }
+            DomainEvent::Rematch {
+                room_id,
+                player_id,
+                marker,
+                action,
+            } => {
+                info!(
+                    player_id = %player_id,
+                    room_id = %room_id,
+                    marker = ?marker,
+                    action = ?action,
+                    "rematch_event"
+                );
+            }
+            DomainEvent::ChatMessage {
+                room_id, player_id, ..
+            } => {
+                info!(player_id = %player_id, room_id = %room_id, "chat_message_sent");
+            }
+        }
+    }
+}
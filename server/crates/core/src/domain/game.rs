@@ -1,20 +1,44 @@
 use crate::{
-    error::AppError,
-    models::{Board, GameState, Marker, PlayerInfo, Status},
+    domain::Position,
+    error::{AppError, InvalidMoveReason},
+    models::{Board, GameState, Marker, MatchFormat, PlayerInfo, RuleSet, Series, Status},
 };
 use anyhow::Result;
 
 #[derive(Debug)]
 pub struct GameEngine {
     pub state: GameState,
+    history: Vec<[usize; 2]>,
+    start: Option<Position>,
 }
 
 impl GameEngine {
-    pub fn new(difficulty: Option<u8>) -> Self {
-        Self {
-            state: GameState::new(difficulty, None, None),
+    /// Games start from `start` when given, rematches included.
+    pub fn new(
+        difficulty: Option<u8>,
+        rules: RuleSet,
+        format: MatchFormat,
+        start: Option<Position>,
+    ) -> Self {
+        let mut state = GameState::new(difficulty, rules, None, None);
+        state.series = Series::new(format);
+        let mut engine = Self {
+            state,
+            history: Vec::new(),
+            start: None,
+        };
+        if let Some(position) = start {
+            engine.set_position(&position);
+            engine.start = Some(position);
         }
+        engine
     }
+
+    fn set_position(&mut self, position: &Position) {
+        self.state.board = position.board.clone();
+        self.state.next_board = position.next_board;
+    }
+
     pub fn make_move(&mut self, mv: [usize; 2]) -> Result<(), AppError> {
         self.validate_move(mv)?;
         self.apply_move(mv)?;
@@ -26,28 +50,45 @@ impl GameEngine {
     fn validate_move(&self, mv: [usize; 2]) -> Result<(), AppError> {
         let a = mv[0];
         let b = mv[1];
+        let expected_board = self.state.next_board;
         if self.state.board.status != Status::InProgress {
-            return Err(AppError::game_not_started());
+            return Err(AppError::invalid_move(
+                InvalidMoveReason::GameNotInProgress,
+                None,
+            ));
         }
 
-        if a >= 9 || b >= 9 {
-            return Err(AppError::invalid_move());
+        let cells = self.state.rules.board_size.cells();
+        if a >= cells || b >= cells {
+            return Err(AppError::invalid_move(
+                InvalidMoveReason::OutOfRange,
+                expected_board,
+            ));
         }
 
         let target_board = &self.state.board.boards[a];
 
-        if let Some(required_board) = self.state.next_board
+        if let Some(required_board) = expected_board
             && required_board != a
         {
-            return Err(AppError::invalid_move());
+            return Err(AppError::invalid_move(
+                InvalidMoveReason::WrongBoard,
+                expected_board,
+            ));
         }
 
-        if target_board.status != Status::InProgress {
-            return Err(AppError::invalid_move());
+        if !self.state.rules.is_playable(target_board) {
+            return Err(AppError::invalid_move(
+                InvalidMoveReason::BoardDecided,
+                expected_board,
+            ));
         }
 
         if target_board.cells[b] != Marker::Empty {
-            return Err(AppError::invalid_move());
+            return Err(AppError::invalid_move(
+                InvalidMoveReason::CellOccupied,
+                expected_board,
+            ));
         }
 
         Ok(())
@@ -68,26 +109,18 @@ impl GameEngine {
     }
 
     fn check_board_win(&mut self, board_idx: usize) -> Result<(), AppError> {
-        let win_conditions = [
-            [0, 1, 2],
-            [3, 4, 5],
-            [6, 7, 8],
-            [0, 3, 6],
-            [1, 4, 7],
-            [2, 5, 8],
-            [0, 4, 8],
-            [2, 4, 6],
-        ];
-
+        let rules = self.state.rules;
         let board = &mut self.state.board.boards[board_idx];
+        // Under `won_boards_playable` a won board keeps its first winner.
+        if board.status != Status::InProgress {
+            return Ok(());
+        }
 
         // Check for win
-        for condition in win_conditions {
-            if board.cells[condition[0]] != Marker::Empty
-                && board.cells[condition[0]] == board.cells[condition[1]]
-                && board.cells[condition[0]] == board.cells[condition[2]]
-            {
-                board.status = Status::Won(board.cells[condition[0]]);
+        for condition in rules.board_size.lines() {
+            let first = board.cells[condition[0]];
+            if first != Marker::Empty && condition.iter().all(|&i| board.cells[i] == first) {
+                board.status = Status::Won(rules.sub_board_winner(first));
                 return Ok(());
             }
         }
@@ -101,33 +134,19 @@ impl GameEngine {
     }
 
     fn check_game_win(&mut self) -> Result<(), AppError> {
-        let win_conditions = [
-            [0, 1, 2],
-            [3, 4, 5],
-            [6, 7, 8],
-            [0, 3, 6],
-            [1, 4, 7],
-            [2, 5, 8],
-            [0, 4, 8],
-            [2, 4, 6],
-        ];
-
         let current_player_marker = self.state.players[self.state.current_index].marker;
-
-        for condition in win_conditions {
-            if self.state.board.boards[condition[0]].status == Status::Won(current_player_marker)
-                && self.state.board.boards[condition[1]].status
-                    == Status::Won(current_player_marker)
-                && self.state.board.boards[condition[2]].status
-                    == Status::Won(current_player_marker)
+        let rules = self.state.rules;
+
+        // A drawn board counting for both sides, or a misère sub-board, can
+        // complete the opponent's line too, so the mover is checked first.
+        for marker in [current_player_marker, !current_player_marker] {
+            if rules
+                .board_size
+                .lines()
+                .iter()
+                .any(|condition| rules.completes_line(&self.state.board, condition, marker))
             {
-                if current_player_marker == Marker::X {
-                    self.state.board.status = Status::Won(Marker::X);
-                    self.state.score[0] += 1;
-                } else {
-                    self.state.board.status = Status::Won(Marker::O);
-                    self.state.score[1] += 1;
-                }
+                self.declare_result(Status::Won(rules.game_winner(marker)));
                 return Ok(());
             }
         }
@@ -138,16 +157,29 @@ impl GameEngine {
             .board
             .boards
             .iter()
-            .all(|board| board.status != Status::InProgress)
+            .all(|board| !rules.is_playable(board))
         {
-            self.state.board.status = Status::Draw;
+            self.declare_result(rules.exhausted_status(&self.state.board));
         }
 
         Ok(())
     }
 
+    fn declare_result(&mut self, status: Status) {
+        self.state.board.status = status;
+        match status {
+            Status::Won(Marker::X) => self.state.score[0] += 1,
+            Status::Won(Marker::O) => self.state.score[1] += 1,
+            _ => {}
+        }
+    }
+
     fn update_next_board(&mut self, target_board: usize) {
-        if self.state.board.boards[target_board].status == Status::InProgress {
+        if self
+            .state
+            .rules
+            .is_playable(&self.state.board.boards[target_board])
+        {
             self.state.next_board = Some(target_board);
         } else {
             self.state.next_board = None;
@@ -155,6 +187,7 @@ impl GameEngine {
     }
     fn update_last_move(&mut self, mv: [usize; 2]) {
         self.state.last_move = Some(mv);
+        self.history.push(mv);
     }
 
     pub fn get_current_player(&self) -> PlayerInfo {
@@ -172,11 +205,15 @@ impl GameEngine {
     pub fn push_player(&mut self, player: PlayerInfo) {
         let marker = player.marker;
         self.state.players.push(player);
-        if self.state.players.len() == 2 && marker == Marker::X {
+        if self.state.players.len() == 2 && marker == self.state.side_to_move() {
             self.state.toggle_players();
         }
     }
 
+    pub fn get_history(&self) -> &[[usize; 2]] {
+        &self.history
+    }
+
     pub fn get_board(&self) -> Board {
         self.state.board.clone()
     }
@@ -233,12 +270,65 @@ impl GameEngine {
         self.state.pending_draw = None;
     }
 
+    pub fn decline_draw(&mut self) {
+        self.state.pending_draw = None;
+        self.state.draw_declined_at = Some(self.history.len());
+    }
+
+    /// How many more moves must be played before another draw offer, given
+    /// `cooldown` moves between a declined offer and the next.
+    pub fn draw_offer_wait(&self, cooldown: usize) -> usize {
+        self.state
+            .draw_declined_at
+            .map_or(0, |at| (at + cooldown).saturating_sub(self.history.len()))
+    }
+
+    /// Gives every player the other marker. The score follows the players.
+    pub fn swap_markers(&mut self) {
+        for player in &mut self.state.players {
+            player.marker = !player.marker;
+        }
+        self.state.score.swap(0, 1);
+        self.state.series.swap_markers();
+    }
+
+    /// Adds the finished game to the series. Returns the match result once
+    /// this game decides it.
+    pub fn record_result(&mut self) -> Option<Status> {
+        let (status, score) = (self.state.board.status, self.state.score);
+        self.state.series.record(status, score)
+    }
+
     pub fn rematch_game(&mut self, difficulty: Option<u8>) {
+        let (first_mover, bot_strength, adaptive) = (
+            self.state.first_mover,
+            self.state.bot_strength,
+            self.state.adaptive,
+        );
+        let mut series = self.state.series.clone();
+        let score = if series.next_game() {
+            [0, 0]
+        } else {
+            self.state.score
+        };
         self.state = GameState::new(
             difficulty,
+            self.state.rules,
             Some(self.state.players.clone()),
-            Some(self.state.score),
+            Some(score),
         );
+        self.state.first_mover = first_mover;
+        self.state.series = series;
+        self.state.bot_strength = bot_strength;
+        self.state.adaptive = adaptive;
+        if let Some(position) = self.start.clone() {
+            self.set_position(&position);
+        }
+        let to_move = self.state.side_to_move();
+        if let Some(index) = self.state.players.iter().position(|p| p.marker == to_move) {
+            self.state.current_index = index;
+        }
+        self.history.clear();
         self.set_board_status(Status::InProgress);
     }
 
@@ -1,11 +1,17 @@
 mod bot_room_rules;
+mod events;
 mod game;
 mod local_room_rules;
+mod position;
+mod room_lifecycle;
 mod room_rules;
 mod standard_room_rules;
 
 pub use bot_room_rules::BotRoomRules;
+pub use events::DomainEvent;
 pub use game::GameEngine;
 pub use local_room_rules::LocalRoomRules;
+pub use position::Position;
+pub use room_lifecycle::{RoomLifecycle, RoomLifecycleEvent, RoomPhase, RoomTransition};
 pub use room_rules::RoomRules;
 pub use standard_room_rules::StandardRoomRules;
@@ -0,0 +1,387 @@
+use crate::{
+    error::AppError,
+    models::{Board, BoardSize, Grid, MacroBoard, Marker, RuleSet, Status},
+};
+use std::fmt::{self, Write};
+
+/// A starting position written as `<boards> <side to move> <next board>`.
+///
+/// Boards are listed row by row and separated by `/`, each as its cells row
+/// by row using `X`, `O` and `.`. The next board is a 0-based index, or `-`
+/// when the side to move may play anywhere. Under `won_boards_playable` a
+/// sub-board can hold lines for both players; it is then followed by `=X` or
+/// `=O` naming the player it counts for. The empty 3×3 game is
+/// `........./........./........./........./........./........./........./........./......... X -`.
+#[derive(Debug, Clone)]
+pub struct Position {
+    pub board: Board,
+    pub to_move: Marker,
+    pub next_board: Option<usize>,
+}
+
+impl Position {
+    /// Parses `text` and checks that it can arise in a game played under
+    /// `rules`: X moves first, sub-board statuses follow from their cells,
+    /// the game is still undecided and `next_board` matches a move the
+    /// previous player could have made.
+    pub fn parse(text: &str, rules: RuleSet) -> Result<Self, AppError> {
+        let size = rules.board_size;
+        let fields: Vec<&str> = text.split_whitespace().collect();
+        let [boards, to_move, next_board] = fields[..] else {
+            return Err(AppError::invalid_position(
+                "three fields: boards, side to move and next board",
+            ));
+        };
+
+        let boards: Vec<MacroBoard> = boards
+            .split('/')
+            .map(|cells| parse_sub_board(cells, rules))
+            .collect::<Result<_, _>>()?;
+        if boards.len() != size.cells() {
+            return Err(AppError::invalid_position(format!(
+                "{} sub-boards separated by '/'",
+                size.cells()
+            )));
+        }
+        let board = Board {
+            boards: Grid::from_slice(&boards).expect("sub-board count checked above"),
+            status: Status::WaitingForPlayers,
+        };
+
+        let to_move = match to_move {
+            "X" | "x" => Marker::X,
+            "O" | "o" => Marker::O,
+            _ => return Err(AppError::invalid_position("side to move to be X or O")),
+        };
+        let count = |marker| {
+            board
+                .boards
+                .iter()
+                .flat_map(|b| b.cells.iter())
+                .filter(|&&cell| cell == marker)
+                .count()
+        };
+        let (xs, os) = (count(Marker::X), count(Marker::O));
+        let expected = match xs.checked_sub(os) {
+            Some(0) => Marker::X,
+            Some(1) => Marker::O,
+            _ => {
+                return Err(AppError::invalid_position(
+                    "X to have as many pieces as O, or one more",
+                ));
+            }
+        };
+        if to_move != expected {
+            return Err(AppError::invalid_position(format!(
+                "{expected:?} to move with {xs} X and {os} O pieces"
+            )));
+        }
+
+        let decided = [Marker::X, Marker::O].into_iter().any(|marker| {
+            size.lines()
+                .iter()
+                .any(|line| rules.completes_line(&board, line, marker))
+        });
+        if decided || board.boards.iter().all(|b| !rules.is_playable(b)) {
+            return Err(AppError::invalid_position("a game that is not yet decided"));
+        }
+
+        let next_board = match next_board {
+            "-" => None,
+            index => Some(
+                index
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&index| index < size.cells())
+                    .ok_or_else(|| {
+                        AppError::invalid_position(format!(
+                            "next board to be '-' or 0 to {}",
+                            size.cells() - 1
+                        ))
+                    })?,
+            ),
+        };
+
+        // The previous move was cell `k` of some board and sent the side to
+        // move to board `k`, or anywhere if board `k` was no longer playable.
+        let previous = !to_move;
+        let reachable = match next_board {
+            None if xs + os == 0 => true,
+            None => board.boards.iter().any(|b| {
+                b.cells.iter().enumerate().any(|(cell, &marker)| {
+                    marker == previous && !rules.is_playable(&board.boards[cell])
+                })
+            }),
+            Some(next) => {
+                rules.is_playable(&board.boards[next])
+                    && board.boards.iter().any(|b| b.cells[next] == previous)
+            }
+        };
+        if !reachable {
+            return Err(AppError::invalid_position(
+                "a next board the previous move could have sent the side to move to",
+            ));
+        }
+
+        Ok(Self {
+            board,
+            to_move,
+            next_board,
+        })
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, board) in self.board.boards.iter().enumerate() {
+            if index > 0 {
+                f.write_char('/')?;
+            }
+            for cell in &board.cells {
+                f.write_char(match cell {
+                    Marker::X => 'X',
+                    Marker::O => 'O',
+                    Marker::Empty => '.',
+                })?;
+            }
+            let size = [BoardSize::Three, BoardSize::Four]
+                .into_iter()
+                .find(|size| size.cells() == board.cells.len());
+            if let (Some(size), Status::Won(holder)) = (size, board.status)
+                && has_line(&board.cells, size, Marker::X)
+                && has_line(&board.cells, size, Marker::O)
+            {
+                write!(f, "={holder:?}")?;
+            }
+        }
+        write!(f, " {:?} ", self.to_move)?;
+        match self.next_board {
+            Some(index) => write!(f, "{index}"),
+            None => f.write_char('-'),
+        }
+    }
+}
+
+fn has_line(cells: &[Marker], size: BoardSize, marker: Marker) -> bool {
+    size.lines()
+        .iter()
+        .any(|line| line.iter().all(|&i| cells[i] == marker))
+}
+
+fn parse_sub_board(text: &str, rules: RuleSet) -> Result<MacroBoard, AppError> {
+    let size = rules.board_size;
+    let (text, holder) = match text.split_once('=') {
+        Some((cells, holder)) => (cells, Some(holder)),
+        None => (text, None),
+    };
+    let cells: Vec<Marker> = text
+        .chars()
+        .map(|c| match c {
+            'X' | 'x' => Ok(Marker::X),
+            'O' | 'o' => Ok(Marker::O),
+            '.' => Ok(Marker::Empty),
+            _ => Err(AppError::invalid_position("cells written as X, O or .")),
+        })
+        .collect::<Result<_, _>>()?;
+    if cells.len() != size.cells() {
+        return Err(AppError::invalid_position(format!(
+            "{} cells in every sub-board",
+            size.cells()
+        )));
+    }
+
+    let (x_line, o_line) = (
+        has_line(&cells, size, Marker::X),
+        has_line(&cells, size, Marker::O),
+    );
+    if holder.is_some() && !(x_line && o_line) {
+        return Err(AppError::invalid_position(
+            "'=' only after a sub-board with lines for both X and O",
+        ));
+    }
+    let status = match (x_line, o_line) {
+        // Only a won board that stays playable can gain a second line.
+        (true, true) if rules.won_boards_playable => match holder {
+            Some("X" | "x") => Status::Won(Marker::X),
+            Some("O" | "o") => Status::Won(Marker::O),
+            _ => {
+                return Err(AppError::invalid_position(
+                    "'=X' or '=O' after a sub-board with lines for both X and O",
+                ));
+            }
+        },
+        (true, true) => {
+            return Err(AppError::invalid_position(
+                "no sub-board with lines for both X and O",
+            ));
+        }
+        (true, false) => Status::Won(rules.sub_board_winner(Marker::X)),
+        (false, true) => Status::Won(rules.sub_board_winner(Marker::O)),
+        _ if !cells.contains(&Marker::Empty) => Status::Draw,
+        _ => Status::InProgress,
+    };
+    Ok(MacroBoard {
+        cells: Grid::from_slice(&cells).expect("cell count checked above"),
+        status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ValidationError;
+
+    const EMPTY: &str = ".........";
+    const START: &str = "........./........./........./........./........./........./........./........./......... X -";
+
+    /// Joins `boards`, padded with empty sub-boards to nine, with `rest`.
+    fn text(boards: &[&str], rest: &str) -> String {
+        let mut boards = boards.to_vec();
+        boards.resize(9, EMPTY);
+        format!("{} {rest}", boards.join("/"))
+    }
+
+    fn rejection(text: &str, rules: RuleSet) -> String {
+        match Position::parse(text, rules) {
+            Err(AppError::Validation(ValidationError::InvalidFormat {
+                expected_format, ..
+            })) => expected_format,
+            other => panic!("expected a position error for {text:?}, got {other:?}"),
+        }
+    }
+
+    fn playable() -> RuleSet {
+        RuleSet {
+            won_boards_playable: true,
+            ..RuleSet::default()
+        }
+    }
+
+    #[test]
+    fn empty_position_round_trips() {
+        let position = Position::parse(START, RuleSet::default()).unwrap();
+        assert_eq!(position.to_move, Marker::X);
+        assert_eq!(position.next_board, None);
+        assert_eq!(position.to_string(), START);
+    }
+
+    #[test]
+    fn doubly_completed_board_round_trips_when_won_boards_stay_playable() {
+        let text = text(&["XXXOOO...=O"], "X 3");
+        let position = Position::parse(&text, playable()).unwrap();
+        assert_eq!(position.board.boards[0].status, Status::Won(Marker::O));
+        assert_eq!(position.to_string(), text);
+    }
+
+    #[test]
+    fn rejects_wrong_field_count() {
+        let error = rejection(&text(&[], "X"), RuleSet::default());
+        assert!(error.starts_with("three fields"), "{error}");
+    }
+
+    #[test]
+    fn rejects_wrong_sub_board_count() {
+        let text = format!("{} X -", [EMPTY; 8].join("/"));
+        assert_eq!(
+            rejection(&text, RuleSet::default()),
+            "9 sub-boards separated by '/'"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_cell() {
+        let error = rejection(&text(&["....?...."], "X -"), RuleSet::default());
+        assert_eq!(error, "cells written as X, O or .");
+    }
+
+    #[test]
+    fn rejects_wrong_cell_count() {
+        let error = rejection(&text(&["........"], "X -"), RuleSet::default());
+        assert_eq!(error, "9 cells in every sub-board");
+    }
+
+    #[test]
+    fn rejects_lines_for_both_players() {
+        let doubly = text(&["XXXOOO..."], "X -");
+        assert_eq!(
+            rejection(&doubly, RuleSet::default()),
+            "no sub-board with lines for both X and O"
+        );
+        assert_eq!(
+            rejection(&doubly, playable()),
+            "'=X' or '=O' after a sub-board with lines for both X and O"
+        );
+        assert_eq!(
+            rejection(&text(&["XXXOO....=X", "O"], "O -"), playable()),
+            "'=' only after a sub-board with lines for both X and O"
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_side_to_move() {
+        let error = rejection(&text(&[], "Z -"), RuleSet::default());
+        assert_eq!(error, "side to move to be X or O");
+    }
+
+    #[test]
+    fn rejects_impossible_piece_counts() {
+        let error = rejection(&text(&["XX......."], "O -"), RuleSet::default());
+        assert_eq!(error, "X to have as many pieces as O, or one more");
+    }
+
+    #[test]
+    fn rejects_wrong_side_to_move() {
+        let error = rejection(&text(&["X........"], "X 0"), RuleSet::default());
+        assert_eq!(error, "O to move with 1 X and 0 O pieces");
+    }
+
+    #[test]
+    fn rejects_decided_games() {
+        let won = text(
+            &[
+                "XXXO.....",
+                "XXXO.....",
+                "XXXO.....",
+                "O........",
+                "O........",
+                "O........",
+                "O........",
+                "O........",
+                "O........",
+            ],
+            "X -",
+        );
+        assert_eq!(
+            rejection(&won, RuleSet::default()),
+            "a game that is not yet decided"
+        );
+
+        let drawn = [["XOXXOOOXX"; 5].as_slice(), ["OXOOXXXOO"; 4].as_slice()].concat();
+        assert_eq!(
+            rejection(&text(&drawn, "O -"), RuleSet::default()),
+            "a game that is not yet decided"
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_range_next_board() {
+        for next in ["9", "a"] {
+            let error = rejection(&text(&[], &format!("X {next}")), RuleSet::default());
+            assert_eq!(error, "next board to be '-' or 0 to 8");
+        }
+    }
+
+    #[test]
+    fn rejects_unreachable_next_board() {
+        let expected = "a next board the previous move could have sent the side to move to";
+        assert_eq!(rejection(&text(&[], "X 4"), RuleSet::default()), expected);
+        assert_eq!(
+            rejection(&text(&["X........"], "O 4"), RuleSet::default()),
+            expected
+        );
+        assert_eq!(
+            rejection(&text(&["X........"], "O -"), RuleSet::default()),
+            expected
+        );
+    }
+}
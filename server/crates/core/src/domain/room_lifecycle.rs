@@ -0,0 +1,114 @@
+use crate::error::AppError;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, ToSchema)]
+pub enum RoomPhase {
+    #[default]
+    Created,
+    Waiting,
+    Playing,
+    Paused,
+    Finished,
+    Closing,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub enum RoomLifecycleEvent {
+    Opened,
+    GameStarted,
+    GamePaused,
+    GameResumed,
+    GameFinished,
+    RematchStarted,
+    ClosingStarted,
+}
+
+impl RoomLifecycleEvent {
+    pub fn target(&self) -> RoomPhase {
+        match self {
+            RoomLifecycleEvent::Opened => RoomPhase::Waiting,
+            RoomLifecycleEvent::GameStarted
+            | RoomLifecycleEvent::GameResumed
+            | RoomLifecycleEvent::RematchStarted => RoomPhase::Playing,
+            RoomLifecycleEvent::GamePaused => RoomPhase::Paused,
+            RoomLifecycleEvent::GameFinished => RoomPhase::Finished,
+            RoomLifecycleEvent::ClosingStarted => RoomPhase::Closing,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub struct RoomTransition {
+    pub from: RoomPhase,
+    pub to: RoomPhase,
+    pub event: RoomLifecycleEvent,
+}
+
+/// Tracks where a room is in its life and rejects transitions that make no
+/// sense from the current phase.
+#[derive(Debug, Default)]
+pub struct RoomLifecycle {
+    phase: RoomPhase,
+}
+
+impl RoomLifecycle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn phase(&self) -> RoomPhase {
+        self.phase
+    }
+
+    pub fn can_apply(&self, event: RoomLifecycleEvent) -> bool {
+        use RoomLifecycleEvent::*;
+        use RoomPhase::*;
+
+        matches!(
+            (self.phase, event),
+            (Created, Opened)
+                | (Waiting, GameStarted)
+                | (Playing, GamePaused)
+                | (Paused, GameResumed)
+                | (Playing | Paused, GameFinished)
+                | (Finished, RematchStarted)
+                | (
+                    Created | Waiting | Playing | Paused | Finished,
+                    ClosingStarted
+                )
+        )
+    }
+
+    pub fn apply(&mut self, event: RoomLifecycleEvent) -> Result<RoomTransition, AppError> {
+        self.ensure_open()?;
+        if !self.can_apply(event) {
+            return Err(AppError::invalid_room_transition(self.phase, event));
+        }
+
+        let transition = RoomTransition {
+            from: self.phase,
+            to: event.target(),
+            event,
+        };
+        self.phase = transition.to;
+        Ok(transition)
+    }
+
+    /// Applies the first of `events` the current phase allows, checking and
+    /// moving in one step. Returns `None` when none of them applies.
+    pub fn apply_first(&mut self, events: &[RoomLifecycleEvent]) -> Option<RoomTransition> {
+        let event = events
+            .iter()
+            .copied()
+            .find(|&event| self.can_apply(event))?;
+        self.apply(event).ok()
+    }
+
+    pub fn ensure_open(&self) -> Result<(), AppError> {
+        if self.phase == RoomPhase::Closing {
+            return Err(AppError::room_closed());
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,4 @@
-use std::env;
+use std::time::Duration;
 
 use crate::{
     domain::RoomRules,
@@ -6,8 +6,21 @@ use crate::{
     models::{Marker, Status},
 };
 
-#[derive(Default)]
-pub struct StandardRoomRules;
+pub struct StandardRoomRules {
+    cleanup_timeout: Duration,
+}
+
+impl StandardRoomRules {
+    pub fn new(cleanup_timeout: Duration) -> Self {
+        Self { cleanup_timeout }
+    }
+}
+
+impl Default for StandardRoomRules {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(60))
+    }
+}
 
 impl RoomRules for StandardRoomRules {
     fn can_join_room(
@@ -57,13 +70,8 @@ impl RoomRules for StandardRoomRules {
         Status::Won(!leaving_player_marker)
     }
 
-    fn get_cleanup_timeout(&self) -> std::time::Duration {
-        std::time::Duration::from_secs(
-            env::var("ROOM_CLEANUP_TIMEOUT_SECS")
-                .unwrap_or_else(|_| "60".to_string())
-                .parse()
-                .unwrap_or(60),
-        )
+    fn get_cleanup_timeout(&self) -> Duration {
+        self.cleanup_timeout
     }
 
     fn get_max_players(&self) -> usize {
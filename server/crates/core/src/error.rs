@@ -1,6 +1,13 @@
 #![allow(unused)]
-use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
-use serde::Serialize;
+use crate::{
+    domain::{RoomLifecycleEvent, RoomPhase},
+    models::MatchFormat,
+};
+use axum::{
+    extract::rejection::{JsonRejection, PathRejection, QueryRejection},
+    http::StatusCode,
+};
+use serde::{Serialize, Serializer};
 use thiserror::Error;
 use utoipa::ToSchema;
 
@@ -37,12 +44,18 @@ pub enum AppError {
 
     #[error("Not found: {message}")]
     NotFound { message: String },
+
+    #[error("Service unavailable: {message}")]
+    Unavailable { message: String },
 }
 
 #[derive(Error, Debug, Clone, Serialize, ToSchema)]
 pub enum GameError {
-    #[error("Invalid move")]
-    InvalidMove,
+    #[error("Invalid move: {reason}")]
+    InvalidMove {
+        reason: InvalidMoveReason,
+        expected_board: Option<usize>,
+    },
 
     #[error("Game has not started yet")]
     NotStarted,
@@ -60,6 +73,24 @@ pub enum GameError {
     AIMoveFailed,
 }
 
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+pub enum InvalidMoveReason {
+    #[error("move is out of range")]
+    OutOfRange,
+
+    #[error("move must be played in the required board")]
+    WrongBoard,
+
+    #[error("board is already decided")]
+    BoardDecided,
+
+    #[error("cell is already occupied")]
+    CellOccupied,
+
+    #[error("game is not in progress")]
+    GameNotInProgress,
+}
+
 #[derive(Error, Debug, Clone, Serialize, ToSchema)]
 pub enum RoomError {
     #[error("Room not found")]
@@ -73,6 +104,12 @@ pub enum RoomError {
 
     #[error("Room is Closed")]
     Closed,
+
+    #[error("Cannot apply {event:?} while room is {from:?}")]
+    InvalidTransition {
+        from: RoomPhase,
+        event: RoomLifecycleEvent,
+    },
 }
 
 #[derive(Error, Debug, Clone, Serialize, ToSchema)]
@@ -101,7 +138,214 @@ pub enum SanitizeError {
     Empty,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[schema(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    GameInvalidMove,
+    GameNotStarted,
+    GameNotPlayerTurn,
+    GameOngoing,
+    GameEnded,
+    GameAiMoveFailed,
+    RoomNotFound,
+    RoomFull,
+    RoomInvalidPassword,
+    RoomClosed,
+    RoomInvalidTransition,
+    PlayerNotFound,
+    ValidationInvalidFormat,
+    ValidationMissingField,
+    MessageTooLong,
+    MessageEmpty,
+    Internal,
+    BadRequest,
+    Forbidden,
+    NotFound,
+    Unavailable,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::GameInvalidMove => "GAME_INVALID_MOVE",
+            ErrorCode::GameNotStarted => "GAME_NOT_STARTED",
+            ErrorCode::GameNotPlayerTurn => "GAME_NOT_PLAYER_TURN",
+            ErrorCode::GameOngoing => "GAME_ONGOING",
+            ErrorCode::GameEnded => "GAME_ENDED",
+            ErrorCode::GameAiMoveFailed => "GAME_AI_MOVE_FAILED",
+            ErrorCode::RoomNotFound => "ROOM_NOT_FOUND",
+            ErrorCode::RoomFull => "ROOM_FULL",
+            ErrorCode::RoomInvalidPassword => "ROOM_INVALID_PASSWORD",
+            ErrorCode::RoomClosed => "ROOM_CLOSED",
+            ErrorCode::RoomInvalidTransition => "ROOM_INVALID_TRANSITION",
+            ErrorCode::PlayerNotFound => "PLAYER_NOT_FOUND",
+            ErrorCode::ValidationInvalidFormat => "VALIDATION_INVALID_FORMAT",
+            ErrorCode::ValidationMissingField => "VALIDATION_MISSING_FIELD",
+            ErrorCode::MessageTooLong => "MESSAGE_TOO_LONG",
+            ErrorCode::MessageEmpty => "MESSAGE_EMPTY",
+            ErrorCode::Internal => "INTERNAL",
+            ErrorCode::BadRequest => "BAD_REQUEST",
+            ErrorCode::Forbidden => "FORBIDDEN",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::Unavailable => "UNAVAILABLE",
+        }
+    }
+}
+
+impl Serialize for ErrorCode {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[derive(Serialize, Debug, Clone, ToSchema)]
+#[serde(untagged)]
+pub enum ErrorDetails {
+    InvalidFormat {
+        field: String,
+        expected_format: String,
+    },
+    MissingField {
+        field: String,
+    },
+    TooLong {
+        max: usize,
+    },
+    InvalidMove {
+        reason: InvalidMoveReason,
+        expected_board: Option<usize>,
+    },
+    InvalidTransition {
+        from: RoomPhase,
+        event: RoomLifecycleEvent,
+    },
+}
+
+#[derive(Serialize, Debug, Clone, ToSchema)]
+pub struct ErrorPayload {
+    pub code: ErrorCode,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<ErrorDetails>,
+}
+
+impl From<&AppError> for ErrorPayload {
+    fn from(err: &AppError) -> Self {
+        Self {
+            code: err.code(),
+            message: err.to_string(),
+            details: err.details(),
+        }
+    }
+}
+
 impl AppError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::Game(err) => match err {
+                GameError::InvalidMove { .. } => ErrorCode::GameInvalidMove,
+                GameError::NotStarted => ErrorCode::GameNotStarted,
+                GameError::NotPlayerTurn => ErrorCode::GameNotPlayerTurn,
+                GameError::Ongoing => ErrorCode::GameOngoing,
+                GameError::Ended => ErrorCode::GameEnded,
+                GameError::AIMoveFailed => ErrorCode::GameAiMoveFailed,
+            },
+            AppError::Room(err) => match err {
+                RoomError::NotFound => ErrorCode::RoomNotFound,
+                RoomError::Full => ErrorCode::RoomFull,
+                RoomError::InvalidPassword => ErrorCode::RoomInvalidPassword,
+                RoomError::Closed => ErrorCode::RoomClosed,
+                RoomError::InvalidTransition { .. } => ErrorCode::RoomInvalidTransition,
+            },
+            AppError::Player(PlayerError::NotFound) => ErrorCode::PlayerNotFound,
+            AppError::Validation(err) => match err {
+                ValidationError::InvalidFormat { .. } => ErrorCode::ValidationInvalidFormat,
+                ValidationError::MissingField { .. } => ErrorCode::ValidationMissingField,
+            },
+            AppError::SanitizeError(err) => match err {
+                SanitizeError::TooLong { .. } => ErrorCode::MessageTooLong,
+                SanitizeError::Empty => ErrorCode::MessageEmpty,
+            },
+            AppError::Internal { .. } => ErrorCode::Internal,
+            AppError::BadRequest { .. } => ErrorCode::BadRequest,
+            AppError::Forbidden { .. } => ErrorCode::Forbidden,
+            AppError::NotFound { .. } => ErrorCode::NotFound,
+            AppError::Unavailable { .. } => ErrorCode::Unavailable,
+        }
+    }
+
+    pub fn details(&self) -> Option<ErrorDetails> {
+        match self {
+            AppError::Validation(ValidationError::InvalidFormat {
+                field,
+                expected_format,
+            }) => Some(ErrorDetails::InvalidFormat {
+                field: field.clone(),
+                expected_format: expected_format.clone(),
+            }),
+            AppError::Validation(ValidationError::MissingField { field }) => {
+                Some(ErrorDetails::MissingField {
+                    field: field.clone(),
+                })
+            }
+            AppError::SanitizeError(SanitizeError::TooLong { max }) => {
+                Some(ErrorDetails::TooLong { max: *max })
+            }
+            AppError::Game(GameError::InvalidMove {
+                reason,
+                expected_board,
+            }) => Some(ErrorDetails::InvalidMove {
+                reason: *reason,
+                expected_board: *expected_board,
+            }),
+            AppError::Room(RoomError::InvalidTransition { from, event }) => {
+                Some(ErrorDetails::InvalidTransition {
+                    from: *from,
+                    event: *event,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// WebSocket close code sent when this error ends a connection. Codes in
+    /// the 4000 range mirror the matching HTTP status.
+    pub fn close_code(&self) -> u16 {
+        match self {
+            AppError::Room(RoomError::NotFound)
+            | AppError::Player(PlayerError::NotFound)
+            | AppError::NotFound { .. } => 4404,
+            AppError::Room(RoomError::InvalidPassword) => 4401,
+            AppError::Room(RoomError::Full | RoomError::InvalidTransition { .. }) => 4409,
+            AppError::Room(RoomError::Closed) => 4410,
+            AppError::Forbidden { .. } => 4403,
+            AppError::Validation(_) | AppError::SanitizeError(_) | AppError::BadRequest { .. } => {
+                4400
+            }
+            AppError::Game(GameError::AIMoveFailed) | AppError::Internal { .. } => 1011,
+            AppError::Unavailable { .. } => 1013,
+            AppError::Game(_) => 4422,
+        }
+    }
+
+    /// HTTP status for the error, derived from `close_code`.
+    pub fn status_code(&self) -> StatusCode {
+        match self.close_code() {
+            code @ 4000..=4999 => {
+                StatusCode::from_u16(code - 4000).unwrap_or(StatusCode::BAD_REQUEST)
+            }
+            1013 => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn to_payload(&self) -> ErrorPayload {
+        ErrorPayload::from(self)
+    }
+
     pub fn room_not_found() -> Self {
         AppError::Room(RoomError::NotFound)
     }
@@ -117,6 +361,10 @@ impl AppError {
         AppError::Room(RoomError::Closed)
     }
 
+    pub fn invalid_room_transition(from: RoomPhase, event: RoomLifecycleEvent) -> Self {
+        AppError::Room(RoomError::InvalidTransition { from, event })
+    }
+
     pub fn invalid_password() -> Self {
         AppError::Room(RoomError::InvalidPassword)
     }
@@ -125,8 +373,11 @@ impl AppError {
         AppError::Player(PlayerError::NotFound)
     }
 
-    pub fn invalid_move() -> Self {
-        AppError::Game(GameError::InvalidMove)
+    pub fn invalid_move(reason: InvalidMoveReason, expected_board: Option<usize>) -> Self {
+        AppError::Game(GameError::InvalidMove {
+            reason,
+            expected_board,
+        })
     }
 
     pub fn not_player_turn() -> Self {
@@ -170,6 +421,51 @@ impl AppError {
             expected_format: "Beginner, Medium, Hard, or Expert".to_string(),
         })
     }
+    pub fn invalid_bot_config(expected: impl Into<String>) -> Self {
+        AppError::Validation(ValidationError::InvalidFormat {
+            field: "bot_config".to_string(),
+            expected_format: expected.into(),
+        })
+    }
+    pub fn unsupported_protocol_version() -> Self {
+        AppError::Validation(ValidationError::InvalidFormat {
+            field: "version".to_string(),
+            expected_format: "1 or 2".to_string(),
+        })
+    }
+    pub fn unsupported_encoding() -> Self {
+        AppError::Validation(ValidationError::InvalidFormat {
+            field: "encoding".to_string(),
+            expected_format: "Json, or MessagePack with protocol version 2".to_string(),
+        })
+    }
+    pub fn invalid_position(expected: impl Into<String>) -> Self {
+        AppError::Validation(ValidationError::InvalidFormat {
+            field: "position".to_string(),
+            expected_format: expected.into(),
+        })
+    }
+    pub fn invalid_match_format() -> Self {
+        AppError::Validation(ValidationError::InvalidFormat {
+            field: "match_format".to_string(),
+            expected_format: format!(
+                "Open, BestOf with an odd count up to {0}, or FirstTo from 1 to {0}",
+                MatchFormat::MAX_GAMES
+            ),
+        })
+    }
+    pub fn adaptive_difficulty_requires_bot() -> Self {
+        AppError::Validation(ValidationError::InvalidFormat {
+            field: "adaptive_difficulty".to_string(),
+            expected_format: "false outside BotRoom".to_string(),
+        })
+    }
+    pub fn unsupported_board_size() -> Self {
+        AppError::Validation(ValidationError::InvalidFormat {
+            field: "rules.board_size".to_string(),
+            expected_format: "3x3; larger boards are not playable in the client yet".to_string(),
+        })
+    }
     pub fn local_room_cannot_be_public() -> Self {
         AppError::Validation(ValidationError::InvalidFormat {
             field: "is_public".to_string(),
@@ -184,6 +480,24 @@ impl AppError {
         }
     }
 
+    pub fn server_draining() -> Self {
+        AppError::Unavailable {
+            message: "Server is not accepting new games right now".to_string(),
+        }
+    }
+
+    pub fn draw_offer_too_soon(moves: usize) -> Self {
+        AppError::Forbidden {
+            message: format!("Wait {moves} more moves before offering another draw"),
+        }
+    }
+
+    pub fn player_removed() -> Self {
+        AppError::Forbidden {
+            message: "You were removed from this room".to_string(),
+        }
+    }
+
     pub fn unsupported_room_type() -> Self {
         Self::BadRequest {
             message: "Unsupported room type".to_string(),
@@ -213,6 +527,14 @@ impl From<serde_json::Error> for AppError {
     }
 }
 
+impl From<rmp_serde::decode::Error> for AppError {
+    fn from(err: rmp_serde::decode::Error) -> Self {
+        AppError::BadRequest {
+            message: format!("MessagePack parsing error: {}", err),
+        }
+    }
+}
+
 impl From<anyhow::Error> for AppError {
     fn from(err: anyhow::Error) -> Self {
         AppError::Internal {
@@ -1,11 +1,18 @@
+pub mod config;
 pub mod domain;
 pub mod error;
 pub mod models;
 
-pub use domain::{BotRoomRules, GameEngine, LocalRoomRules, RoomRules, StandardRoomRules};
+pub use config::ServerConfig;
+pub use domain::{
+    BotRoomRules, DomainEvent, GameEngine, LocalRoomRules, Position, RoomLifecycle,
+    RoomLifecycleEvent, RoomPhase, RoomRules, RoomTransition, StandardRoomRules,
+};
 pub use error::AppError;
 pub use models::{
-    Action, Board, BotLevel, ClientMessage, GameState, GetRoomQuery, MacroBoard, Marker, Player,
-    PlayerAction, PlayerInfo, Room, RoomInfo, RoomType, SerizlizedPlayer, ServerMessage, Status,
-    WebSocketQuery,
+    Action, AdaptiveRecord, Board, BoardSize, BotConfig, BotEngine, BotLevel, BotStrength,
+    Capability, ClientMessage, CustomBot, Encoding, FirstMover, GameState, GetRoomQuery,
+    LobbyEvent, MacroBoard, Marker, MarkerPreference, MatchFormat, Player, PlayerAction,
+    PlayerInfo, PlayerOverview, ProtocolVersion, Room, RoomDetail, RoomInfo, RoomOverview,
+    RoomSort, RoomType, RuleSet, Series, SerizlizedPlayer, ServerMessage, Status, WebSocketQuery,
 };
@@ -0,0 +1,29 @@
+use super::{BotLevel, GameState, Marker, RoomInfo};
+use crate::domain::RoomPhase;
+use serde::Serialize;
+
+/// What operators see for a room, private ones included.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomOverview {
+    pub info: RoomInfo,
+    pub bot_level: Option<BotLevel>,
+    pub phase: RoomPhase,
+    pub pending_cleanup: bool,
+    pub players: Vec<PlayerOverview>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlayerOverview {
+    pub id: String,
+    pub marker: Marker,
+    pub is_bot: bool,
+    pub connected: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RoomDetail {
+    #[serde(flatten)]
+    pub overview: RoomOverview,
+    pub game: GameState,
+    pub moves: Vec<[usize; 2]>,
+}
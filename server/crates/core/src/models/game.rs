@@ -1,7 +1,7 @@
-use super::PlayerInfo;
+use super::{BoardSize, BotStrength, Grid, MatchFormat, PlayerInfo, Series};
 use anyhow::Result;
 use serde::{Deserialize, Serialize, Serializer};
-use std::ops::Not;
+use std::{cmp::Ordering, ops::Not};
 use utoipa::ToSchema;
 
 #[derive(Debug, Clone, Copy, PartialEq, Deserialize, Eq, ToSchema, Default)]
@@ -65,26 +65,141 @@ impl Serialize for Status {
         }
     }
 }
-#[derive(Debug, Default, Clone, Copy, Serialize, ToSchema)]
+#[derive(Debug, Clone, Copy, Default, Serialize, ToSchema)]
 pub struct MacroBoard {
-    pub cells: [Marker; 9],
+    #[schema(value_type = Vec<Marker>)]
+    pub cells: Grid<Marker>,
     pub status: Status,
 }
 
+impl MacroBoard {
+    pub fn new(size: BoardSize) -> Self {
+        Self {
+            cells: Grid::filled(size.cells(), Marker::Empty),
+            status: Status::InProgress,
+        }
+    }
+}
+
+/// Boards and cells are indexed row by row, so a 3×3 game serializes as
+/// nine boards of nine cells.
 #[derive(Clone, Serialize, Debug, ToSchema)]
 pub struct Board {
-    pub boards: [MacroBoard; 9],
+    #[schema(value_type = Vec<MacroBoard>)]
+    pub boards: Grid<MacroBoard>,
     pub status: Status,
 }
-impl Default for Board {
-    fn default() -> Self {
+impl Board {
+    pub fn new(size: BoardSize) -> Self {
         Board {
-            boards: [MacroBoard::default(); 9],
+            boards: Grid::filled(size.cells(), MacroBoard::new(size)),
             status: Status::WaitingForPlayers,
         }
     }
 }
-#[derive(Clone, Debug)]
+impl Default for Board {
+    fn default() -> Self {
+        Self::new(BoardSize::default())
+    }
+}
+/// Rule variants chosen at room creation. The default is the classic game:
+/// decided sub-boards are closed and sending a player to one grants a free move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, ToSchema)]
+#[serde(default)]
+pub struct RuleSet {
+    /// Won sub-boards keep accepting moves until they are full, and sending
+    /// a player to one is not a free move.
+    pub won_boards_playable: bool,
+    /// A drawn sub-board counts towards a line for both players. A line still
+    /// needs at least one sub-board the player actually won.
+    pub drawn_boards_count_for_both: bool,
+    /// When no line is completed, the player who won more sub-boards wins
+    /// instead of the game ending in a draw.
+    pub majority_tiebreak: bool,
+    /// Completing a macro line loses the game. Combined with
+    /// `majority_tiebreak`, the player with fewer sub-boards wins.
+    pub misere: bool,
+    /// Completing a line inside a sub-board hands that sub-board to the
+    /// opponent.
+    pub misere_sub_boards: bool,
+    pub board_size: BoardSize,
+}
+
+impl RuleSet {
+    pub fn is_playable(&self, board: &MacroBoard) -> bool {
+        match board.status {
+            Status::InProgress => true,
+            Status::Won(_) if self.won_boards_playable => board.cells.contains(&Marker::Empty),
+            _ => false,
+        }
+    }
+
+    /// Who a sub-board goes to when `marker` completes a line in it.
+    pub fn sub_board_winner(&self, marker: Marker) -> Marker {
+        if self.misere_sub_boards {
+            !marker
+        } else {
+            marker
+        }
+    }
+
+    /// Who wins the game when `marker` completes a macro line.
+    pub fn game_winner(&self, marker: Marker) -> Marker {
+        if self.misere { !marker } else { marker }
+    }
+
+    pub fn counts_for(&self, status: Status, marker: Marker) -> bool {
+        status == Status::Won(marker)
+            || (self.drawn_boards_count_for_both && status == Status::Draw)
+    }
+
+    pub fn completes_line(&self, board: &Board, line: &[usize], marker: Marker) -> bool {
+        line.iter()
+            .all(|&i| self.counts_for(board.boards[i].status, marker))
+            && line
+                .iter()
+                .any(|&i| board.boards[i].status == Status::Won(marker))
+    }
+
+    /// Result of a game with no playable sub-board left and no completed line.
+    pub fn exhausted_status(&self, board: &Board) -> Status {
+        if !self.majority_tiebreak {
+            return Status::Draw;
+        }
+        let won_by = |marker| {
+            board
+                .boards
+                .iter()
+                .filter(|b| b.status == Status::Won(marker))
+                .count()
+        };
+        match won_by(Marker::X).cmp(&won_by(Marker::O)) {
+            Ordering::Greater => Status::Won(self.game_winner(Marker::X)),
+            Ordering::Less => Status::Won(self.game_winner(Marker::O)),
+            Ordering::Equal => Status::Draw,
+        }
+    }
+}
+
+/// A player's results against an adaptive bot across every game in the room.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct AdaptiveRecord {
+    pub games: usize,
+    /// One point per win and half a point per draw.
+    pub points: f64,
+}
+
+impl AdaptiveRecord {
+    /// Adds a game the player scored `points` in and returns their running
+    /// score, from 0.0 to 1.0.
+    pub fn add(&mut self, points: f64) -> f64 {
+        self.games += 1;
+        self.points += points;
+        self.points / self.games as f64
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
 pub struct GameState {
     pub players: Vec<PlayerInfo>,
     pub current_index: usize,
@@ -94,26 +209,58 @@ pub struct GameState {
     pub score: [usize; 2],
     pub pending_rematch: Option<String>,
     pub pending_draw: Option<String>,
+    /// Moves played when a draw offer was last declined.
+    pub draw_declined_at: Option<usize>,
     pub difficulty: u8,
+    pub rules: RuleSet,
+    pub first_mover: Marker,
+    pub series: Series,
+    pub bot_strength: Option<BotStrength>,
+    pub adaptive: AdaptiveRecord,
 }
 impl GameState {
     pub fn new(
         difficulty: Option<u8>,
+        rules: RuleSet,
         players: Option<Vec<PlayerInfo>>,
         score: Option<[usize; 2]>,
     ) -> Self {
         Self {
             players: players.unwrap_or_default(),
             current_index: 0,
-            board: Board::default(),
+            board: Board::new(rules.board_size),
             next_board: None,
             last_move: None,
             score: score.unwrap_or([0, 0]),
             pending_rematch: None,
             pending_draw: None,
+            draw_declined_at: None,
             difficulty: difficulty.unwrap_or_default(),
+            rules,
+            first_mover: Marker::X,
+            series: Series::new(MatchFormat::Open),
+            bot_strength: None,
+            adaptive: AdaptiveRecord::default(),
+        }
+    }
+    /// Moves alternate from `first_mover`, so the counts on the board decide
+    /// whose turn it is.
+    pub fn side_to_move(&self) -> Marker {
+        let count = |marker| {
+            self.board
+                .boards
+                .iter()
+                .flat_map(|b| b.cells.iter())
+                .filter(|&&cell| cell == marker)
+                .count()
+        };
+        if count(self.first_mover) > count(!self.first_mover) {
+            !self.first_mover
+        } else {
+            self.first_mover
         }
     }
+
     pub fn toggle_players(&mut self) {
         self.current_index = 1 - self.current_index;
     }
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize, Serializer};
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+    sync::OnceLock,
+};
+use utoipa::ToSchema;
+
+/// Cells in a sub-board, and sub-boards in a game, on the largest size.
+pub const MAX_CELLS: usize = 16;
+
+/// Side length of both the macro board and every sub-board: `4x4` is sixteen
+/// 4×4 sub-boards laid out 4×4, where a line needs four in a row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize, ToSchema)]
+pub enum BoardSize {
+    #[default]
+    #[serde(rename = "3x3")]
+    Three,
+    #[serde(rename = "4x4")]
+    Four,
+}
+
+impl BoardSize {
+    pub fn side(self) -> usize {
+        match self {
+            BoardSize::Three => 3,
+            BoardSize::Four => 4,
+        }
+    }
+
+    /// Cells per sub-board, which is also the number of sub-boards.
+    pub fn cells(self) -> usize {
+        self.side() * self.side()
+    }
+
+    /// Rows, then columns, then both diagonals.
+    pub fn lines(self) -> &'static [Vec<usize>] {
+        static THREE: OnceLock<Vec<Vec<usize>>> = OnceLock::new();
+        static FOUR: OnceLock<Vec<Vec<usize>>> = OnceLock::new();
+        let lines = match self {
+            BoardSize::Three => &THREE,
+            BoardSize::Four => &FOUR,
+        };
+        lines.get_or_init(|| win_lines(self.side()))
+    }
+
+    pub fn corners(self) -> [usize; 4] {
+        let n = self.side();
+        [0, n - 1, n * (n - 1), n * n - 1]
+    }
+
+    /// The middle cell, or the middle 2×2 block on even sides.
+    pub fn is_center(self, index: usize) -> bool {
+        let n = self.side();
+        let near_middle = |i: usize| (2 * i).abs_diff(n - 1) <= 1;
+        near_middle(index / n) && near_middle(index % n)
+    }
+}
+
+fn win_lines(n: usize) -> Vec<Vec<usize>> {
+    let rows = (0..n).map(|r| (0..n).map(|c| r * n + c).collect());
+    let columns = (0..n).map(|c| (0..n).map(|r| r * n + c).collect());
+    let diagonal = (0..n).map(|i| i * n + i).collect();
+    let anti_diagonal = (0..n).map(|i| i * n + (n - 1 - i)).collect();
+    rows.chain(columns)
+        .chain([diagonal, anti_diagonal])
+        .collect()
+}
+
+/// Inline storage for the cells of a sub-board or the sub-boards of a game,
+/// so cloning a game state, which the search does at every node, never
+/// allocates. Dereferences to the entries in use.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Grid<T> {
+    len: u8,
+    items: [T; MAX_CELLS],
+}
+
+impl<T: Copy + Default> Grid<T> {
+    pub fn filled(len: usize, value: T) -> Self {
+        assert!(len <= MAX_CELLS, "grid of {len} exceeds {MAX_CELLS}");
+        let mut items = [T::default(); MAX_CELLS];
+        items[..len].fill(value);
+        Self {
+            len: len as u8,
+            items,
+        }
+    }
+
+    /// `None` when `items` has more than `MAX_CELLS` entries.
+    pub fn from_slice(items: &[T]) -> Option<Self> {
+        (items.len() <= MAX_CELLS).then(|| {
+            let mut grid = Self::filled(items.len(), T::default());
+            grid.copy_from_slice(items);
+            grid
+        })
+    }
+}
+
+impl<T: Copy + Default> Default for Grid<T> {
+    fn default() -> Self {
+        Self::filled(0, T::default())
+    }
+}
+
+impl<T> Deref for Grid<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.items[..usize::from(self.len)]
+    }
+}
+
+impl<T> DerefMut for Grid<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.items[..usize::from(self.len)]
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Grid<T> {
+    type Item = &'a T;
+    type IntoIter = std::slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Serialize> Serialize for Grid<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
@@ -0,0 +1,38 @@
+use super::{GetRoomQuery, RoomInfo};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+#[derive(Serialize, Debug, Clone, ToSchema)]
+#[serde(tag = "event", content = "data")]
+pub enum LobbyEvent {
+    Snapshot { rooms: Vec<RoomInfo> },
+    RoomCreated(RoomInfo),
+    RoomUpdated(RoomInfo),
+    RoomFilled(RoomInfo),
+    RoomRemoved { id: String },
+    Announcement { message: String },
+    ShutdownWarning { remaining_secs: u64 },
+}
+
+impl LobbyEvent {
+    /// The event as a lobby watching with `query` should see it. A room that
+    /// stops matching, say because it filled up, is sent as removed.
+    pub fn for_query(&self, query: &GetRoomQuery) -> Option<LobbyEvent> {
+        match self {
+            LobbyEvent::RoomCreated(info) => query.matches(info).then(|| self.clone()),
+            LobbyEvent::RoomUpdated(info) | LobbyEvent::RoomFilled(info) => {
+                if query.matches(info) {
+                    Some(self.clone())
+                } else {
+                    Some(LobbyEvent::RoomRemoved {
+                        id: info.id.clone(),
+                    })
+                }
+            }
+            LobbyEvent::Snapshot { .. }
+            | LobbyEvent::RoomRemoved { .. }
+            | LobbyEvent::Announcement { .. }
+            | LobbyEvent::ShutdownWarning { .. } => Some(self.clone()),
+        }
+    }
+}
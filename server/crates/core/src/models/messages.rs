@@ -1,27 +1,24 @@
-use super::{Board, PlayerInfo};
-use crate::{error::AppError, models::Marker};
+use super::{
+    Board, BotStrength, Capability, Encoding, PlayerInfo, ProtocolVersion, RoomInfo, Status,
+};
+use crate::{
+    error::{AppError, ErrorPayload},
+    models::Marker,
+};
 use anyhow::Result;
-use axum::extract::ws::Message;
+use axum::extract::ws::{CloseFrame, Message};
 use serde::{Deserialize, Serialize};
 use serde_json::to_string;
+use std::cmp::Reverse;
 use utoipa::{IntoParams, ToSchema};
 
 #[derive(Deserialize, Clone, Debug, ToSchema)]
 pub enum ClientMessage {
-    TextMessage {
-        content: String,
-    },
-    GameUpdate {
-        mv: [usize; 2],
-    },
-    RematchRequest {
-        action: Action,
-    },
-    DrawRequest {
-        action: Action,
-    },
+    TextMessage { content: String },
+    GameUpdate { mv: [usize; 2] },
+    RematchRequest { action: Action },
+    DrawRequest { action: Action },
     Resign,
-    #[cfg(not(debug_assertions))]
     Pong,
 }
 #[derive(Serialize, Debug, Deserialize, Clone, ToSchema)]
@@ -37,11 +34,66 @@ pub struct WebSocketQuery {
     #[serde(default)]
     pub is_reconnecting: bool,
     pub player_id: Option<String>,
+    pub version: Option<u8>,
+    pub encoding: Option<Encoding>,
 }
 
-#[derive(Deserialize, ToSchema)]
+#[derive(Debug, Deserialize, ToSchema, IntoParams)]
+#[serde(default)]
 pub struct GetRoomQuery {
+    /// Case-insensitive substring of the room name.
     pub name: Option<String>,
+    pub page: usize,
+    pub per_page: usize,
+    pub sort: RoomSort,
+    pub hide_full: bool,
+    pub hide_in_progress: bool,
+}
+
+impl Default for GetRoomQuery {
+    fn default() -> Self {
+        Self {
+            name: None,
+            page: 1,
+            per_page: 20,
+            sort: RoomSort::default(),
+            hide_full: true,
+            hide_in_progress: false,
+        }
+    }
+}
+
+impl GetRoomQuery {
+    pub const MAX_PER_PAGE: usize = 100;
+
+    pub fn matches(&self, room: &RoomInfo) -> bool {
+        room.matches_name(self.name.as_deref())
+            && !(self.hide_full && room.is_full())
+            && !(self.hide_in_progress && room.is_in_progress())
+    }
+
+    /// Filters, sorts and paginates `rooms`, returning the page together with
+    /// the number of rooms that matched before pagination.
+    pub fn apply(&self, rooms: Vec<RoomInfo>) -> (usize, Vec<RoomInfo>) {
+        let mut rooms: Vec<RoomInfo> = rooms.into_iter().filter(|r| self.matches(r)).collect();
+        match self.sort {
+            RoomSort::Newest => rooms.sort_by_key(|r| Reverse(r.created_at)),
+            RoomSort::Name => rooms.sort_by_cached_key(|r| r.name.to_lowercase()),
+        }
+
+        let total = rooms.len();
+        let per_page = self.per_page.clamp(1, Self::MAX_PER_PAGE);
+        let offset = self.page.saturating_sub(1).saturating_mul(per_page);
+        let page = rooms.into_iter().skip(offset).take(per_page).collect();
+        (total, page)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema)]
+pub enum RoomSort {
+    #[default]
+    Newest,
+    Name,
 }
 
 #[derive(Serialize, Debug, Clone, ToSchema)]
@@ -64,11 +116,19 @@ pub enum ServerMessage {
         player: PlayerInfo,
     },
     GameUpdate {
-        board: Board,
+        board: Box<Board>,
         next_player: PlayerInfo,
         next_board: Option<usize>,
         last_move: Option<[usize; 2]>,
         score: [usize; 2],
+        game_number: usize,
+        bot_strength: Option<BotStrength>,
+    },
+    /// Sent once a game decides the room's match.
+    MatchOver {
+        result: Status,
+        score: [usize; 2],
+        results: Vec<Status>,
     },
     PlayerUpdate {
         action: PlayerAction,
@@ -84,14 +144,75 @@ pub enum ServerMessage {
     },
     #[serde(skip_serializing)]
     WebsocketMessage(Message),
-    #[cfg(not(debug_assertions))]
     Ping,
-    Error(AppError),
+    Hello {
+        #[schema(value_type = u8)]
+        version: ProtocolVersion,
+        #[schema(value_type = Vec<u8>)]
+        supported_versions: Vec<ProtocolVersion>,
+        capabilities: Vec<Capability>,
+        encoding: Encoding,
+    },
+    Error(ErrorPayload),
+    /// The error frame of clients without `StructuredErrors`.
+    #[serde(rename = "Error")]
+    LegacyError(AppError),
+    Announcement {
+        message: String,
+    },
+    RoomClosed {
+        reason: String,
+    },
+    ShutdownWarning {
+        remaining_secs: u64,
+    },
 }
 impl ServerMessage {
+    pub fn hello(version: ProtocolVersion, encoding: Encoding) -> Self {
+        ServerMessage::Hello {
+            version,
+            supported_versions: ProtocolVersion::SUPPORTED.to_vec(),
+            capabilities: version.capabilities(),
+            encoding,
+        }
+    }
+
+    pub fn error(err: &AppError, protocol: ProtocolVersion) -> Self {
+        if protocol.supports(Capability::StructuredErrors) {
+            ServerMessage::Error(err.to_payload())
+        } else {
+            ServerMessage::LegacyError(err.clone())
+        }
+    }
+
+    /// Asks the send task to close the socket with `code` and `reason`.
+    pub fn close(code: u16, reason: &str) -> Self {
+        ServerMessage::WebsocketMessage(Message::Close(Some(CloseFrame {
+            code,
+            reason: truncate_close_reason(reason).into(),
+        })))
+    }
+
     pub fn to_json(&self) -> Result<String> {
         Ok(to_string(self)?)
     }
+
+    pub fn to_msgpack(&self) -> Result<Vec<u8>> {
+        Ok(rmp_serde::to_vec_named(self)?)
+    }
+}
+
+/// Close frame reasons are limited to 123 bytes.
+fn truncate_close_reason(reason: &str) -> &str {
+    const MAX_CLOSE_REASON_LEN: usize = 123;
+    if reason.len() <= MAX_CLOSE_REASON_LEN {
+        return reason;
+    }
+    let mut end = MAX_CLOSE_REASON_LEN;
+    while !reason.is_char_boundary(end) {
+        end -= 1;
+    }
+    &reason[..end]
 }
 
 #[derive(Serialize, Clone, Debug, ToSchema)]
@@ -100,4 +221,44 @@ pub enum PlayerAction {
     Left,
     Disconnected(u64),
     Reconnected,
+    /// Sent to each player when a rematch swaps markers.
+    MarkerChanged,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{Value, json};
+
+    fn error_frame(err: &AppError, protocol: ProtocolVersion) -> Value {
+        serde_json::from_str(&ServerMessage::error(err, protocol).to_json().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn v1_errors_keep_the_app_error_shape() {
+        assert_eq!(
+            error_frame(&AppError::room_not_found(), ProtocolVersion::V1),
+            json!({"event": "Error", "data": {"type": "Room", "details": "NotFound"}})
+        );
+        assert_eq!(
+            error_frame(&AppError::not_allowed(), ProtocolVersion::V1),
+            json!({"event": "Error", "data": {"type": "Forbidden", "details": {"message": "Forbidden"}}})
+        );
+    }
+
+    #[test]
+    fn v2_errors_are_structured() {
+        assert_eq!(
+            error_frame(&AppError::room_not_found(), ProtocolVersion::V2),
+            json!({"event": "Error", "data": {"code": "ROOM_NOT_FOUND", "message": "Room error: Room not found"}})
+        );
+        assert_eq!(
+            error_frame(&AppError::too_long_text_message(10), ProtocolVersion::V2),
+            json!({"event": "Error", "data": {
+                "code": "MESSAGE_TOO_LONG",
+                "message": AppError::too_long_text_message(10).to_string(),
+                "details": {"max": 10},
+            }})
+        );
+    }
 }
@@ -1,12 +1,27 @@
+mod admin;
 mod game;
+mod geometry;
+mod lobby;
 mod messages;
 mod player;
+mod protocol;
+mod record;
 mod room;
+mod series;
 
-pub use game::{Board, GameState, MacroBoard, Marker, Status};
+pub use admin::{PlayerOverview, RoomDetail, RoomOverview};
+pub use game::{AdaptiveRecord, Board, GameState, MacroBoard, Marker, RuleSet, Status};
+pub use geometry::{BoardSize, Grid, MAX_CELLS};
+pub use lobby::LobbyEvent;
 pub use messages::{
-    Action, ClientMessage, GetRoomQuery, PlayerAction, SerizlizedPlayer, ServerMessage,
+    Action, ClientMessage, GetRoomQuery, PlayerAction, RoomSort, SerizlizedPlayer, ServerMessage,
     WebSocketQuery,
 };
 pub use player::{Player, PlayerInfo};
-pub use room::{BotLevel, Room, RoomInfo, RoomType};
+pub use protocol::{Capability, Encoding, ProtocolVersion};
+pub use record::GameRecord;
+pub use room::{
+    BotConfig, BotEngine, BotLevel, BotStrength, CustomBot, FirstMover, MarkerPreference, Room,
+    RoomInfo, RoomType,
+};
+pub use series::{MatchFormat, Series};
@@ -0,0 +1,126 @@
+use super::ServerMessage;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize, Serializer};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum ProtocolVersion {
+    #[default]
+    V1,
+    V2,
+}
+
+impl ProtocolVersion {
+    pub const SUPPORTED: [ProtocolVersion; 2] = [ProtocolVersion::V1, ProtocolVersion::V2];
+    pub const LATEST: ProtocolVersion = ProtocolVersion::V2;
+
+    /// Resolves the version requested by a client, falling back to `V1` for
+    /// clients that predate negotiation.
+    pub fn negotiate(requested: Option<u8>) -> Result<Self, AppError> {
+        match requested {
+            None => Ok(Self::default()),
+            Some(version) => Self::try_from(version),
+        }
+    }
+
+    pub fn negotiate_encoding(&self, requested: Option<Encoding>) -> Result<Encoding, AppError> {
+        match requested.unwrap_or_default() {
+            Encoding::MessagePack if !self.supports(Capability::BinaryEncoding) => {
+                Err(AppError::unsupported_encoding())
+            }
+            encoding => Ok(encoding),
+        }
+    }
+
+    pub fn number(&self) -> u8 {
+        match self {
+            ProtocolVersion::V1 => 1,
+            ProtocolVersion::V2 => 2,
+        }
+    }
+
+    pub fn capabilities(&self) -> Vec<Capability> {
+        match self {
+            ProtocolVersion::V1 => vec![
+                Capability::Chat,
+                Capability::Rematch,
+                Capability::DrawOffer,
+                Capability::Resign,
+                Capability::Reconnect,
+                Capability::Heartbeat,
+            ],
+            ProtocolVersion::V2 => vec![
+                Capability::Chat,
+                Capability::Rematch,
+                Capability::DrawOffer,
+                Capability::Resign,
+                Capability::Reconnect,
+                Capability::Heartbeat,
+                Capability::Hello,
+                Capability::BinaryEncoding,
+                Capability::ServerNotices,
+                Capability::MatchSeries,
+                Capability::StructuredErrors,
+            ],
+        }
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities().contains(&capability)
+    }
+
+    /// Whether a server message exists in this version of the protocol.
+    pub fn can_send(&self, message: &ServerMessage) -> bool {
+        match message {
+            ServerMessage::Hello { .. } => self.supports(Capability::Hello),
+            ServerMessage::Announcement { .. }
+            | ServerMessage::RoomClosed { .. }
+            | ServerMessage::ShutdownWarning { .. } => self.supports(Capability::ServerNotices),
+            ServerMessage::MatchOver { .. } => self.supports(Capability::MatchSeries),
+            _ => true,
+        }
+    }
+}
+
+impl TryFrom<u8> for ProtocolVersion {
+    type Error = AppError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(ProtocolVersion::V1),
+            2 => Ok(ProtocolVersion::V2),
+            _ => Err(AppError::unsupported_protocol_version()),
+        }
+    }
+}
+
+impl Serialize for ProtocolVersion {
+    fn serialize<T>(&self, serializer: T) -> Result<T::Ok, T::Error>
+    where
+        T: Serializer,
+    {
+        serializer.serialize_u8(self.number())
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub enum Capability {
+    Chat,
+    Rematch,
+    DrawOffer,
+    Resign,
+    Reconnect,
+    Heartbeat,
+    Hello,
+    BinaryEncoding,
+    ServerNotices,
+    MatchSeries,
+    StructuredErrors,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default, ToSchema)]
+pub enum Encoding {
+    #[default]
+    Json,
+    MessagePack,
+}
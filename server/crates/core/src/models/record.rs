@@ -0,0 +1,19 @@
+use super::{Board, BotLevel, RoomType, RuleSet, SerizlizedPlayer, Status};
+use serde::Serialize;
+
+/// Everything needed to reconstruct a finished game after the room is gone.
+#[derive(Debug, Clone, Serialize)]
+pub struct GameRecord {
+    pub room_id: String,
+    pub room_type: RoomType,
+    pub bot_level: Option<BotLevel>,
+    pub rules: RuleSet,
+    pub start_position: Option<String>,
+    pub players: Vec<SerizlizedPlayer>,
+    pub moves: Vec<[usize; 2]>,
+    pub board: Board,
+    pub result: Status,
+    pub score: [usize; 2],
+    pub game_number: usize,
+    pub finished_at: u64,
+}
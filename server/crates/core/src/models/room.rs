@@ -1,19 +1,27 @@
 use crate::{
-    domain::GameEngine,
+    config::{BotBehaviour, BotLimits},
+    domain::{
+        DomainEvent, GameEngine, Position, RoomLifecycle, RoomLifecycleEvent, RoomPhase,
+        RoomTransition,
+    },
     error::AppError,
-    models::{Marker, Player, PlayerInfo, ServerMessage},
+    models::{
+        GameRecord, Marker, MatchFormat, Player, PlayerAction, PlayerInfo, PlayerOverview,
+        RoomDetail, RoomOverview, RuleSet, SerizlizedPlayer, ServerMessage, Status,
+    },
 };
 use rand::RngExt;
 use serde::{Deserialize, Serialize};
 use std::{
-    env,
+    collections::HashSet,
     sync::{
         Arc,
         atomic::{AtomicUsize, Ordering},
     },
+    time::{SystemTime, UNIX_EPOCH},
 };
 use tokio::sync::{
-    Mutex,
+    Mutex, broadcast,
     mpsc::{Receiver, Sender},
 };
 use tokio_util::sync::CancellationToken;
@@ -29,13 +37,75 @@ pub struct RoomInfo {
     pub room_type: RoomType,
     #[serde(skip_serializing)]
     pub bot_level: Option<BotLevel>,
+    /// Bot rooms only, instead of `bot_level`.
+    #[serde(skip_serializing)]
+    pub bot_config: Option<BotConfig>,
     #[serde(skip_serializing)]
     pub password: Option<String>,
     #[serde(skip_deserializing)]
     pub is_protected: bool,
+    #[serde(skip_deserializing)]
+    pub player_count: usize,
+    #[serde(skip_deserializing)]
+    pub max_players: usize,
+    #[serde(skip_deserializing)]
+    pub game_status: Status,
+    #[serde(skip_deserializing)]
+    pub created_at: u64,
+    pub rules: RuleSet,
+    /// Starting position in `Position` notation, for practice rooms.
+    pub position: Option<String>,
+    /// Marker given to the first player to join.
+    pub marker_preference: MarkerPreference,
+    /// Ignored when the room starts from a `position`, which decides it.
+    pub first_mover: FirstMover,
+    /// Keeps markers across rematches instead of swapping them.
+    pub fixed_markers: bool,
+    pub match_format: MatchFormat,
+    /// Bot rooms only: the bot's skill follows the player's running score,
+    /// settling where they win about half their games.
+    pub adaptive_difficulty: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, ToSchema)]
+pub enum MarkerPreference {
+    X,
+    O,
+    #[default]
+    Random,
+}
+
+/// `Creator` is the first player to join; in bot rooms `Opponent` lets the
+/// bot start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, ToSchema)]
+pub enum FirstMover {
+    #[default]
+    X,
+    O,
+    Creator,
+    Opponent,
+    Random,
+}
+
+impl RoomInfo {
+    pub fn matches_name(&self, name_filter: Option<&str>) -> bool {
+        name_filter.is_none_or(|filter| {
+            self.name
+                .to_lowercase()
+                .contains(filter.trim().to_lowercase().as_str())
+        })
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.player_count >= self.max_players
+    }
+
+    pub fn is_in_progress(&self) -> bool {
+        self.game_status == Status::InProgress
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum BotLevel {
     Beginner,
     Medium,
@@ -43,6 +113,94 @@ pub enum BotLevel {
     Expert,
 }
 
+impl BotLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BotLevel::Beginner => "beginner",
+            BotLevel::Medium => "medium",
+            BotLevel::Hard => "hard",
+            BotLevel::Expert => "expert",
+        }
+    }
+}
+
+/// A bot picked by engine difficulty or spelled out setting by setting.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize, ToSchema)]
+pub enum BotConfig {
+    /// One of the engine difficulties, 1 to 10.
+    Level(u8),
+    Custom(CustomBot),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize, ToSchema)]
+pub struct CustomBot {
+    pub max_depth: usize,
+    pub max_time_ms: u64,
+    /// Chance from 0.0 to 1.0 of playing a weaker move than the best found.
+    #[serde(default)]
+    pub mistake_chance: f64,
+    #[serde(default)]
+    pub engine: BotEngine,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, ToSchema)]
+pub enum BotEngine {
+    #[default]
+    Minimax,
+    /// Plays any legal move.
+    Random,
+}
+
+impl BotConfig {
+    /// The engine difficulty this bot is closest to, for anything still
+    /// keyed by level.
+    pub fn difficulty(&self) -> u8 {
+        match self {
+            BotConfig::Level(level) => *level,
+            BotConfig::Custom(custom) => custom.max_depth.clamp(1, 10) as u8,
+        }
+    }
+
+    pub fn validate(&self, limits: &BotLimits) -> Result<(), AppError> {
+        match self {
+            BotConfig::Level(level) if !(1..=limits.max_level).contains(level) => Err(
+                AppError::invalid_bot_config(format!("Level from 1 to {}", limits.max_level)),
+            ),
+            BotConfig::Custom(custom) if !(1..=limits.max_depth).contains(&custom.max_depth) => {
+                Err(AppError::invalid_bot_config(format!(
+                    "max_depth from 1 to {}",
+                    limits.max_depth
+                )))
+            }
+            BotConfig::Custom(custom)
+                if !(1..=limits.max_time_ms).contains(&custom.max_time_ms) =>
+            {
+                Err(AppError::invalid_bot_config(format!(
+                    "max_time_ms from 1 to {}",
+                    limits.max_time_ms
+                )))
+            }
+            BotConfig::Custom(custom) if !(0.0..=1.0).contains(&custom.mistake_chance) => Err(
+                AppError::invalid_bot_config("mistake_chance from 0.0 to 1.0"),
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The search settings a bot is currently playing with. `skill` runs from
+/// 1.0 to 10.0 like the configured difficulties, and adaptive rooms move it
+/// between games.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+pub struct BotStrength {
+    pub skill: f64,
+    pub max_depth: usize,
+    pub max_time_ms: u64,
+    pub random_move_chance: f64,
+    pub mistake_chance: f64,
+    pub engine: BotEngine,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Hash, PartialEq, Eq, Default, ToSchema)]
 pub enum RoomType {
     #[default]
@@ -58,42 +216,38 @@ pub struct Room {
     pub player_counter: AtomicUsize,
     pub game: Arc<Mutex<GameEngine>>,
     pub info: RoomInfo,
+    /// Set in bot rooms from the level's configured behaviour.
+    pub bot: Option<BotBehaviour>,
     pub deletion_token: Mutex<Option<CancellationToken>>,
+    pub lifecycle: Mutex<RoomLifecycle>,
+    events: broadcast::Sender<DomainEvent>,
+    removed_players: Mutex<HashSet<String>>,
 }
 
 impl Room {
-    pub fn new(info: RoomInfo, tx: Sender<ServerMessage>) -> Self {
-        let beginner_difficulty = env::var("BOT_BEGINNER_DIFFICULTY")
-            .ok()
-            .and_then(|val| val.parse::<u8>().ok())
-            .unwrap_or(1);
-        let medium_difficulity = env::var("BOT_MEDIUM_DIFFICULTY")
-            .ok()
-            .and_then(|val| val.parse::<u8>().ok())
-            .unwrap_or(4);
-        let hard_difficulity = env::var("BOT_HARD_DIFFICULTY")
-            .ok()
-            .and_then(|val| val.parse::<u8>().ok())
-            .unwrap_or(7);
-        let expert_difficulity = env::var("BOT_EXPERT_DIFFICULTY")
-            .ok()
-            .and_then(|val| val.parse::<u8>().ok())
-            .unwrap_or(10);
-
-        let difficulty = match info.bot_level {
-            Some(BotLevel::Beginner) => Some(beginner_difficulty),
-            Some(BotLevel::Medium) => Some(medium_difficulity),
-            Some(BotLevel::Hard) => Some(hard_difficulity),
-            Some(BotLevel::Expert) => Some(expert_difficulity),
-            None => None,
-        };
+    pub fn new(
+        info: RoomInfo,
+        tx: Sender<ServerMessage>,
+        events: broadcast::Sender<DomainEvent>,
+        difficulty: Option<u8>,
+        bot: Option<BotBehaviour>,
+        start: Option<Position>,
+    ) -> Self {
+        let rules = info.rules;
+        let format = info.match_format;
         Self {
             tx,
             player_counter: AtomicUsize::new(0),
             players: Mutex::new(Vec::new()),
             info,
-            game: Arc::new(Mutex::new(GameEngine::new(difficulty))),
+            bot,
+            game: Arc::new(Mutex::new(GameEngine::new(
+                difficulty, rules, format, start,
+            ))),
             deletion_token: Mutex::new(None),
+            lifecycle: Mutex::new(RoomLifecycle::new()),
+            events,
+            removed_players: Mutex::new(HashSet::new()),
         }
     }
 
@@ -109,15 +263,26 @@ impl Room {
     }
 
     pub async fn add_player(&self, player_id: Option<String>) -> Result<String, AppError> {
-        let marker = if self.get_player_count() == 0 {
-            if rand::rng().random_bool(0.5) {
-                Marker::O
-            } else {
-                Marker::X
+        let is_creator = self.get_player_count() == 0;
+        let marker = if is_creator {
+            match self.info.marker_preference {
+                MarkerPreference::X => Marker::X,
+                MarkerPreference::O => Marker::O,
+                MarkerPreference::Random => random_marker(),
             }
         } else {
             !self.players.lock().await[0].info.marker
         };
+        if is_creator && self.info.position.is_none() {
+            let first_mover = match self.info.first_mover {
+                FirstMover::X => Marker::X,
+                FirstMover::O => Marker::O,
+                FirstMover::Creator => marker,
+                FirstMover::Opponent => !marker,
+                FirstMover::Random => random_marker(),
+            };
+            self.game.lock().await.state.first_mover = first_mover;
+        }
 
         let player = Player::new(player_id, marker);
         let player_id = player.id.clone();
@@ -177,6 +342,227 @@ impl Room {
             .map(f)
     }
 
+    pub async fn snapshot_info(&self) -> RoomInfo {
+        let mut info = self.info.clone();
+        info.player_count = self.get_player_count();
+        info.game_status = self.game.lock().await.get_board_status();
+        info
+    }
+
+    pub async fn phase(&self) -> RoomPhase {
+        self.lifecycle.lock().await.phase()
+    }
+
+    pub async fn ensure_open(&self) -> Result<(), AppError> {
+        self.lifecycle.lock().await.ensure_open()
+    }
+
+    pub async fn transition(&self, event: RoomLifecycleEvent) -> Result<RoomTransition, AppError> {
+        let transition = self.lifecycle.lock().await.apply(event)?;
+        self.publish(DomainEvent::RoomTransitioned {
+            room_id: self.info.id.clone(),
+            transition,
+        });
+        Ok(transition)
+    }
+
+    /// Like `transition`, but for the first of `events` the room's phase
+    /// allows, so callers need not read the phase first and race.
+    pub async fn transition_first(&self, events: &[RoomLifecycleEvent]) -> Option<RoomTransition> {
+        let transition = self.lifecycle.lock().await.apply_first(events)?;
+        self.publish(DomainEvent::RoomTransitioned {
+            room_id: self.info.id.clone(),
+            transition,
+        });
+        Some(transition)
+    }
+
+    pub fn publish(&self, event: DomainEvent) {
+        event.log();
+        // Nobody listening is not an error; the bus is best-effort.
+        let _ = self.events.send(event);
+    }
+
+    pub async fn publish_move(&self, player_id: Option<String>, marker: Marker, mv: [usize; 2]) {
+        let game = self.game.lock().await;
+        self.publish(DomainEvent::MoveMade {
+            room_id: self.info.id.clone(),
+            player_id,
+            marker,
+            mv,
+            board: game.get_board(),
+            next_board: game.get_next_board(),
+            next_player: game.get_next_player().marker,
+        });
+    }
+
+    /// Starts the game once every seat is taken, or resumes it after a
+    /// reconnection. Returns `None` when the room is in neither state.
+    pub async fn start_or_resume(&self) -> Result<Option<RoomTransition>, AppError> {
+        // Held across the transition so its subscribers see the game running.
+        let mut game = self.game.lock().await;
+        let Some(transition) = self
+            .transition_first(&[
+                RoomLifecycleEvent::GameStarted,
+                RoomLifecycleEvent::GameResumed,
+            ])
+            .await
+        else {
+            return Ok(None);
+        };
+        game.set_board_status(Status::InProgress);
+        Ok(Some(transition))
+    }
+
+    pub async fn finish_if_decided(&self) -> Result<Option<RoomTransition>, AppError> {
+        let status = self.game.lock().await.get_board_status();
+        if matches!(status, Status::Won(_) | Status::Draw) {
+            let transition = self.transition(RoomLifecycleEvent::GameFinished).await?;
+            self.publish_game_finished().await;
+            return Ok(Some(transition));
+        }
+        Ok(None)
+    }
+
+    pub async fn game_record(&self) -> GameRecord {
+        let players = self
+            .players
+            .lock()
+            .await
+            .iter()
+            .map(|p| SerizlizedPlayer::new(p.info.marker, Some(p.id.clone())))
+            .collect();
+        let game = self.game.lock().await;
+        GameRecord {
+            room_id: self.info.id.clone(),
+            room_type: self.info.room_type.clone(),
+            bot_level: self.info.bot_level.clone(),
+            rules: self.info.rules,
+            start_position: self.info.position.clone(),
+            players,
+            moves: game.get_history().to_vec(),
+            board: game.get_board(),
+            result: game.get_board_status(),
+            score: game.state.score,
+            game_number: game.state.series.game_number,
+            finished_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Call once the final status has been written to the game.
+    pub async fn publish_game_finished(&self) {
+        let match_result = self.game.lock().await.record_result();
+        let record = self.game_record().await;
+        self.publish(DomainEvent::GameFinished { record });
+        if let Some(result) = match_result {
+            let game = self.game.lock().await;
+            let _ = self
+                .tx
+                .send(ServerMessage::MatchOver {
+                    result,
+                    score: game.get_score(),
+                    results: game.state.series.results.clone(),
+                })
+                .await;
+        }
+    }
+
+    pub async fn overview(&self) -> RoomOverview {
+        let players = self
+            .players
+            .lock()
+            .await
+            .iter()
+            .map(|p| PlayerOverview {
+                id: p.id.clone(),
+                marker: p.info.marker,
+                is_bot: self.info.room_type == RoomType::BotRoom && p.tx.is_none(),
+                connected: p.tx.as_ref().is_some_and(|tx| !tx.is_closed()),
+            })
+            .collect();
+        RoomOverview {
+            info: self.snapshot_info().await,
+            bot_level: self.info.bot_level.clone(),
+            phase: self.phase().await,
+            pending_cleanup: self.is_pending_cleanup().await,
+            players,
+        }
+    }
+
+    pub async fn detail(&self) -> RoomDetail {
+        let overview = self.overview().await;
+        let game = self.game.lock().await;
+        RoomDetail {
+            overview,
+            game: game.state.clone(),
+            moves: game.get_history().to_vec(),
+        }
+    }
+
+    /// Sends `message` to every connected player. Returns how many were reached.
+    pub async fn send_to_players(&self, message: ServerMessage) -> usize {
+        self.players
+            .lock()
+            .await
+            .iter()
+            .filter_map(|p| p.tx.as_ref())
+            .filter(|tx| tx.send(message.clone()).is_ok())
+            .count()
+    }
+
+    /// Tells every player why the room is going away and closes their sockets.
+    pub async fn close_connections(&self, reason: &str) {
+        self.send_to_players(ServerMessage::RoomClosed {
+            reason: reason.to_string(),
+        })
+        .await;
+        self.send_to_players(ServerMessage::close(
+            AppError::room_closed().close_code(),
+            reason,
+        ))
+        .await;
+    }
+
+    /// Closes the player's socket and keeps them from reconnecting.
+    pub async fn remove_player(&self, player_id: &String, reason: &str) -> Result<(), AppError> {
+        let player = self.get_player(player_id).await?;
+        self.removed_players.lock().await.insert(player_id.clone());
+        if let Some(tx) = player.tx {
+            let _ = tx.send(ServerMessage::close(
+                AppError::player_removed().close_code(),
+                reason,
+            ));
+        }
+        Ok(())
+    }
+
+    pub async fn is_removed(&self, player_id: &str) -> bool {
+        self.removed_players.lock().await.contains(player_id)
+    }
+
+    /// Swaps every player's marker for the next game unless the room keeps
+    /// them fixed, and tells each player their new marker. Call with the
+    /// game locked, before `GameEngine::rematch_game`.
+    pub async fn prepare_rematch(&self, game: &mut GameEngine) {
+        if self.info.fixed_markers || self.info.room_type == RoomType::LocalRoom {
+            return;
+        }
+        game.swap_markers();
+        let mut players = self.players.lock().await;
+        for player in players.iter_mut() {
+            player.info.marker = !player.info.marker;
+            if let Some(tx) = &player.tx {
+                let _ = tx.send(ServerMessage::PlayerUpdate {
+                    action: PlayerAction::MarkerChanged,
+                    player: SerizlizedPlayer::new(player.info.marker, Some(player.id.clone())),
+                });
+            }
+        }
+    }
+
     pub async fn is_pending_cleanup(&self) -> bool {
         let guard = self.deletion_token.lock().await;
         guard.is_some()
@@ -204,11 +590,13 @@ impl Room {
     pub async fn get_board_message(&self) -> ServerMessage {
         let game = self.game.lock().await;
         ServerMessage::GameUpdate {
-            board: game.get_board(),
+            board: Box::new(game.get_board()),
             next_player: game.get_next_player(),
             next_board: game.get_next_board(),
             last_move: game.get_last_move(),
             score: game.get_score(),
+            game_number: game.state.series.game_number,
+            bot_strength: game.state.bot_strength,
         }
     }
 
@@ -217,3 +605,11 @@ impl Room {
         let _ = self.tx.send(msg).await;
     }
 }
+
+fn random_marker() -> Marker {
+    if rand::rng().random_bool(0.5) {
+        Marker::O
+    } else {
+        Marker::X
+    }
+}
@@ -0,0 +1,200 @@
+use super::{Marker, Status};
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use utoipa::ToSchema;
+
+/// How many games a room plays before its match is decided.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize, ToSchema)]
+pub enum MatchFormat {
+    /// Rematches keep adding to one running score.
+    #[default]
+    Open,
+    /// At most N games, N odd; a majority of them takes the match.
+    BestOf(u8),
+    /// Draws do not count; the first to N wins takes the match.
+    FirstTo(u8),
+}
+
+impl MatchFormat {
+    pub const MAX_GAMES: u8 = 15;
+
+    pub fn is_valid(self) -> bool {
+        match self {
+            MatchFormat::Open => true,
+            MatchFormat::BestOf(n) => n % 2 == 1 && n <= Self::MAX_GAMES,
+            MatchFormat::FirstTo(n) => (1..=Self::MAX_GAMES).contains(&n),
+        }
+    }
+}
+
+/// Progress through the room's current match. Like the score, results are
+/// written with the markers the players hold now.
+#[derive(Debug, Clone, Serialize)]
+pub struct Series {
+    pub format: MatchFormat,
+    pub game_number: usize,
+    pub results: Vec<Status>,
+    pub result: Option<Status>,
+}
+
+impl Series {
+    pub fn new(format: MatchFormat) -> Self {
+        Self {
+            format,
+            game_number: 1,
+            results: Vec::new(),
+            result: None,
+        }
+    }
+
+    /// Adds a finished game. Returns the match result if this game decided it.
+    pub fn record(&mut self, result: Status, score: [usize; 2]) -> Option<Status> {
+        self.results.push(result);
+        let leader = match score[0].cmp(&score[1]) {
+            Ordering::Greater => Status::Won(Marker::X),
+            Ordering::Less => Status::Won(Marker::O),
+            Ordering::Equal => Status::Draw,
+        };
+        let most_wins = score[0].max(score[1]);
+        let decided = match self.format {
+            MatchFormat::Open => false,
+            MatchFormat::BestOf(n) => {
+                most_wins > usize::from(n / 2) || self.results.len() >= usize::from(n)
+            }
+            MatchFormat::FirstTo(n) => most_wins >= usize::from(n),
+        };
+        self.result = decided.then_some(leader);
+        self.result
+    }
+
+    /// Moves on to the next game. Returns true when that starts a new match,
+    /// in which case the score should start over too.
+    pub fn next_game(&mut self) -> bool {
+        if self.result.is_some() {
+            *self = Self::new(self.format);
+            return true;
+        }
+        self.game_number += 1;
+        false
+    }
+
+    pub fn swap_markers(&mut self) {
+        for status in self.results.iter_mut().chain(self.result.as_mut()) {
+            if let Status::Won(marker) = status {
+                *marker = !*marker;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const X: Status = Status::Won(Marker::X);
+    const O: Status = Status::Won(Marker::O);
+
+    /// Records `results` in order, keeping the score the way a room does,
+    /// and returns what each game reported.
+    fn play(
+        series: &mut Series,
+        score: &mut [usize; 2],
+        results: &[Status],
+    ) -> Vec<Option<Status>> {
+        results
+            .iter()
+            .map(|&result| {
+                match result {
+                    Status::Won(Marker::X) => score[0] += 1,
+                    Status::Won(Marker::O) => score[1] += 1,
+                    _ => {}
+                }
+                let decided = series.record(result, *score);
+                series.next_game();
+                decided
+            })
+            .collect()
+    }
+
+    #[test]
+    fn best_of_is_decided_by_a_majority() {
+        let mut series = Series::new(MatchFormat::BestOf(3));
+        let mut score = [0; 2];
+        assert_eq!(play(&mut series, &mut score, &[X, O]), [None, None]);
+        assert_eq!(series.game_number, 3);
+        assert_eq!(series.record(X, [2, 1]), Some(X));
+
+        let mut series = Series::new(MatchFormat::BestOf(5));
+        assert_eq!(
+            play(&mut series, &mut [0; 2], &[O, O, O]),
+            [None, None, Some(O)]
+        );
+    }
+
+    #[test]
+    fn best_of_ends_after_its_last_game_even_when_drawn() {
+        let mut series = Series::new(MatchFormat::BestOf(3));
+        assert_eq!(
+            play(&mut series, &mut [0; 2], &[Status::Draw, X, Status::Draw]),
+            [None, None, Some(X)]
+        );
+
+        let mut series = Series::new(MatchFormat::BestOf(3));
+        assert_eq!(
+            play(&mut series, &mut [0; 2], &[X, O, Status::Draw]),
+            [None, None, Some(Status::Draw)]
+        );
+    }
+
+    #[test]
+    fn first_to_ignores_draws() {
+        let mut series = Series::new(MatchFormat::FirstTo(2));
+        let mut score = [0; 2];
+        let draws = [Status::Draw; 5];
+        assert_eq!(play(&mut series, &mut score, &draws), [None; 5]);
+        assert_eq!(
+            play(&mut series, &mut score, &[O, X, X]),
+            [None, None, Some(X)]
+        );
+    }
+
+    #[test]
+    fn open_matches_are_never_decided() {
+        let mut series = Series::new(MatchFormat::Open);
+        assert_eq!(play(&mut series, &mut [0; 2], &[X; 20]), [None; 20]);
+        assert_eq!(series.game_number, 21);
+    }
+
+    #[test]
+    fn next_game_starts_a_new_match_once_decided() {
+        let mut series = Series::new(MatchFormat::FirstTo(1));
+        assert!(!series.next_game());
+        assert_eq!(series.game_number, 2);
+
+        assert_eq!(series.record(O, [0, 1]), Some(O));
+        assert!(series.next_game());
+        assert_eq!(series.game_number, 1);
+        assert!(series.results.is_empty());
+        assert_eq!(series.result, None);
+        assert_eq!(series.format, MatchFormat::FirstTo(1));
+    }
+
+    #[test]
+    fn swap_markers_follows_a_rematch() {
+        let mut series = Series::new(MatchFormat::BestOf(3));
+        let mut score = [0; 2];
+        play(&mut series, &mut score, &[X, Status::Draw]);
+
+        // The rematch swaps markers: X's win now belongs to O.
+        series.swap_markers();
+        score.reverse();
+        assert_eq!(series.results, [O, Status::Draw]);
+        assert_eq!(play(&mut series, &mut score, &[O]), [Some(O)]);
+
+        let mut series = Series::new(MatchFormat::FirstTo(1));
+        series.record(X, [1, 0]);
+        series.swap_markers();
+        assert_eq!(series.result, Some(O));
+        assert_eq!(series.results, [O]);
+    }
+}
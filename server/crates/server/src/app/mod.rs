@@ -1,5 +1,8 @@
+pub mod rate_limit;
 mod server;
+pub mod shutdown;
 mod state;
 
+pub use rate_limit::RateLimiter;
 pub use server::start_server;
 pub use state::AppState;
@@ -0,0 +1,165 @@
+use crate::{app::AppState, utils::real_ip::real_client_ip};
+use axum::{
+    Json,
+    extract::{ConnectInfo, MatchedPath, Request, State},
+    http::{Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use governor::{
+    DefaultKeyedRateLimiter, Quota,
+    clock::{Clock, DefaultClock},
+};
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    num::NonZeroU32,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+use tracing::debug;
+use ultimatexo_core::config::{RateLimitPolicy, RateLimitRoute, RateLimitSettings};
+
+type KeyedLimiter = DefaultKeyedRateLimiter<String>;
+
+struct Limiters {
+    settings: RateLimitSettings,
+    default: Arc<KeyedLimiter>,
+    routes: HashMap<RateLimitRoute, Arc<KeyedLimiter>>,
+}
+
+/// Per-client rate limiting whose policies can be swapped while the server
+/// is running. A policy change only resets the buckets it actually touches.
+pub struct RateLimiter {
+    limiters: RwLock<Limiters>,
+}
+
+impl RateLimiter {
+    pub fn new(settings: RateLimitSettings) -> Self {
+        let default = limiter_for(settings.default);
+        let routes = settings
+            .routes
+            .iter()
+            .map(|(route, policy)| (*route, limiter_for(*policy)))
+            .collect();
+        Self {
+            limiters: RwLock::new(Limiters {
+                settings,
+                default,
+                routes,
+            }),
+        }
+    }
+
+    pub fn settings(&self) -> RateLimitSettings {
+        self.read().settings.clone()
+    }
+
+    /// Callers are expected to have validated `settings`.
+    pub fn update(&self, settings: RateLimitSettings) {
+        let mut limiters = self.limiters.write().unwrap_or_else(|e| e.into_inner());
+        if settings.default != limiters.settings.default {
+            limiters.default = limiter_for(settings.default);
+        }
+        let routes = settings
+            .routes
+            .iter()
+            .map(|(route, policy)| {
+                let limiter = match limiters.settings.policy_for(*route) {
+                    Some(current) if current == *policy => limiters.routes[route].clone(),
+                    _ => limiter_for(*policy),
+                };
+                (*route, limiter)
+            })
+            .collect();
+        limiters.routes = routes;
+        limiters.settings = settings;
+    }
+
+    /// `Err` carries how long the client has to wait.
+    pub fn check(&self, route: Option<RateLimitRoute>, key: &str) -> Result<(), Duration> {
+        let limiter = {
+            let limiters = self.read();
+            if !limiters.settings.enabled {
+                return Ok(());
+            }
+            route
+                .and_then(|route| limiters.routes.get(&route))
+                .unwrap_or(&limiters.default)
+                .clone()
+        };
+        limiter
+            .check_key(&key.to_string())
+            .map_err(|not_until| not_until.wait_time_from(DefaultClock::default().now()))
+    }
+
+    /// Drops idle clients and returns how many keys are still tracked.
+    pub fn retain_recent(&self) -> usize {
+        let limiters = self.read();
+        let mut tracked = 0;
+        for limiter in std::iter::once(&limiters.default).chain(limiters.routes.values()) {
+            limiter.retain_recent();
+            tracked += limiter.len();
+        }
+        tracked
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Limiters> {
+        self.limiters.read().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn limiter_for(policy: RateLimitPolicy) -> Arc<KeyedLimiter> {
+    let per_second = NonZeroU32::new(policy.per_second).unwrap_or(NonZeroU32::MIN);
+    let burst = NonZeroU32::new(policy.burst).unwrap_or(NonZeroU32::MIN);
+    Arc::new(DefaultKeyedRateLimiter::keyed(
+        Quota::per_second(per_second).allow_burst(burst),
+    ))
+}
+
+fn route_for(method: &Method, path: &str) -> Option<RateLimitRoute> {
+    let route = match (method, path) {
+        (&Method::GET, "/rooms") => RateLimitRoute::ListRooms,
+        (&Method::POST, "/rooms") => RateLimitRoute::CreateRoom,
+        (_, "/room/{room_id}") => RateLimitRoute::GetRoom,
+        (_, "/client-error") => RateLimitRoute::ClientError,
+        (_, "/health") => RateLimitRoute::Health,
+        (_, "/ws/lobby") => RateLimitRoute::Lobby,
+        (_, "/ws/{room_id}") => RateLimitRoute::Game,
+        (_, path) if path.starts_with("/admin") => RateLimitRoute::Admin,
+        _ => return None,
+    };
+    Some(route)
+}
+
+pub async fn rate_limit(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .and_then(|path| route_for(request.method(), path.as_str()));
+    let client_ip = real_client_ip(request.headers(), addr);
+
+    if let Err(wait) = state.rate_limiter().check(route, &client_ip) {
+        let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+        debug!(
+            route = route.map_or("default", |route| route.as_str()),
+            retry_after, "rate_limited"
+        );
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after.to_string())],
+            Json(json!({
+                "message": format!("Too many requests. Try again in {retry_after}s.")
+            })),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
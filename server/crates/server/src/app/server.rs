@@ -1,66 +1,46 @@
 #[cfg(debug_assertions)]
 use crate::handlers::ApiDoc;
 use crate::{
-    app::state::AppState,
-    handlers::{client_error, create_room, get_room, get_rooms, health_check, websocket_handler},
+    app::{rate_limit, shutdown::graceful_shutdown, state::AppState},
+    handlers::{
+        MetricsToken, announce, client_error, close_room, create_room, get_drain, get_policies,
+        get_room, get_room_detail, get_rooms, health_check, kick_player, list_rooms,
+        lobby_websocket_handler, metrics_handler, require_admin, set_drain, update_bot_level,
+        update_rate_limits, websocket_handler,
+    },
 };
 use anyhow::{Context, Result};
 use axum::{
-    Router,
-    routing::{get, post},
+    Extension, Router, middleware,
+    routing::{get, patch, post, put},
 };
-#[cfg(not(debug_assertions))]
-use std::time::Duration;
 use std::{
-    env,
     net::{SocketAddr, ToSocketAddrs},
     sync::Arc,
+    time::Duration,
 };
-use tokio::signal;
-#[cfg(not(debug_assertions))]
-use tower_governor::GovernorLayer;
-#[cfg(not(debug_assertions))]
-use tower_governor::governor::GovernorConfigBuilder;
-#[cfg(not(debug_assertions))]
-use tower_governor::key_extractor::SmartIpKeyExtractor;
-#[cfg(not(debug_assertions))]
-use tracing::debug;
-use tracing::info;
+use tracing::{debug, error, info};
+use ultimatexo_core::{ServerConfig, config::ListenSettings};
+use ultimatexo_services::metrics;
 #[cfg(debug_assertions)]
 use utoipa::OpenApi;
 #[cfg(debug_assertions)]
 use utoipa_swagger_ui::SwaggerUi;
 
-#[derive(Debug, Clone)]
-struct ServerConfig {
-    host: String,
-    port: u16,
+fn socket_addr(listen: &ListenSettings) -> Result<SocketAddr> {
+    let addr_str = format!("{}:{}", listen.host, listen.port);
+    addr_str
+        .to_socket_addrs()?
+        .next()
+        .context("Could not resolve host to SocketAddr")
 }
 
-impl ServerConfig {
-    fn from_env() -> Result<Self> {
-        let host = env::var("HOST").unwrap_or("localhost".to_string());
-
-        let port = env::var("PORT")
-            .unwrap_or("6767".to_string())
-            .parse::<u16>()
-            .context("PORT must be a valid u16")?;
+fn http_url(listen: &ListenSettings) -> String {
+    format!("http://{}:{}", listen.host, listen.port)
+}
 
-        Ok(Self { host, port })
-    }
-    fn socket_addr(&self) -> Result<SocketAddr> {
-        let addr_str = format!("{}:{}", self.host, self.port);
-        addr_str
-            .to_socket_addrs()?
-            .next()
-            .context("Could not resolve host to SocketAddr")
-    }
-    fn http_url(&self) -> String {
-        format!("http://{}:{}", self.host, self.port)
-    }
-    fn ws_url(&self) -> String {
-        format!("ws://{}:{}", self.host, self.port)
-    }
+fn ws_url(listen: &ListenSettings) -> String {
+    format!("ws://{}:{}", listen.host, listen.port)
 }
 
 fn build_router(state: Arc<AppState>) -> Router {
@@ -69,7 +49,9 @@ fn build_router(state: Arc<AppState>) -> Router {
         .route("/room/{room_id}", get(get_room))
         .route("/client-error", post(client_error))
         .route("/health", get(health_check));
-    let ws_routes = Router::new().route("/{room_id}", get(websocket_handler));
+    let ws_routes = Router::new()
+        .route("/lobby", get(lobby_websocket_handler))
+        .route("/{room_id}", get(websocket_handler));
     let app = Router::new().merge(api_routes).nest("/ws", ws_routes);
 
     #[cfg(debug_assertions)]
@@ -78,88 +60,107 @@ fn build_router(state: Arc<AppState>) -> Router {
     app.with_state(state)
 }
 
-fn log_startup_info(config: &ServerConfig) {
-    info!(url = %config.http_url(), "server_listening");
-    info!(url = %config.ws_url(), "websocket_ready");
-    #[cfg(debug_assertions)]
-    info!(url = %config.http_url(), "swagger_ready");
+fn metrics_router(state: Arc<AppState>, token: Option<&str>) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics_handler))
+        .layer(Extension(MetricsToken(token.map(Arc::from))))
+        .with_state(state)
 }
 
-pub async fn start_server() -> Result<()> {
-    let config = ServerConfig::from_env()?;
-    let state = Arc::new(AppState::new());
-    #[allow(unused_mut)]
-    let mut app = build_router(state);
-
-    #[cfg(not(debug_assertions))]
-    {
-        let governor_conf = GovernorConfigBuilder::default()
-            .key_extractor(SmartIpKeyExtractor)
-            .per_second(2)
-            .burst_size(5)
-            .finish()
-            .context("Failed to build governor config")?;
-
-        let limiter = governor_conf.limiter().clone();
-
-        let cleanup_secs: u64 = env::var("GOVERNOR_CLEANUP_INTERVAL_SECS")
-            .unwrap_or_else(|_| "60".into())
-            .parse()
-            .context("GOVERNOR_CLEANUP_INTERVAL_SECS must be a valid u64")?;
-
+/// `/metrics` is only exposed when it can be protected: on its own bind
+/// address (usually loopback or a private interface), or on the public
+/// listener behind `metrics.token`.
+async fn setup_metrics(config: &ServerConfig, state: Arc<AppState>, app: Router) -> Result<Router> {
+    let token = config.metrics.token.as_deref();
+    if let Some(bind) = &config.metrics.bind {
+        let listener = tokio::net::TcpListener::bind(bind)
+            .await
+            .with_context(|| format!("Failed to bind metrics listener to {}", bind))?;
+        let router = metrics_router(state, token);
         tokio::spawn(async move {
-            let mut interval = tokio::time::interval(Duration::from_secs(cleanup_secs));
-            loop {
-                interval.tick().await;
-                debug!(storage_size = limiter.len(), "rate_limiter_storage");
-                limiter.retain_recent();
+            if let Err(e) = axum::serve(listener, router).await {
+                error!(error = %e, "metrics_server_failed");
             }
         });
-
-        app = app.layer(GovernorLayer::new(governor_conf));
+        info!(bind = %bind, "metrics_ready");
+        return Ok(app);
     }
+    if token.is_some() {
+        info!(url = %format!("{}/metrics", http_url(&config.server)), "metrics_ready");
+        return Ok(app.merge(metrics_router(state, token)));
+    }
+    debug!("metrics_disabled");
+    Ok(app)
+}
+
+/// `/admin` is only mounted when `admin.token` is set.
+fn admin_router(config: &ServerConfig, state: Arc<AppState>) -> Option<Router> {
+    config.admin.token.as_ref()?;
+    let router = Router::new()
+        .route("/admin/rooms", get(list_rooms))
+        .route("/admin/rooms/{room_id}", get(get_room_detail))
+        .route("/admin/rooms/{room_id}/close", post(close_room))
+        .route(
+            "/admin/rooms/{room_id}/players/{player_id}/kick",
+            post(kick_player),
+        )
+        .route("/admin/announcements", post(announce))
+        .route("/admin/drain", get(get_drain).put(set_drain))
+        .route("/admin/policies", get(get_policies))
+        .route("/admin/rate-limits", patch(update_rate_limits))
+        .route("/admin/bot-levels/{level}", put(update_bot_level))
+        .layer(middleware::from_fn_with_state(state.clone(), require_admin))
+        .with_state(state);
+    Some(router)
+}
+
+fn log_startup_info(listen: &ListenSettings) {
+    info!(url = %http_url(listen), "server_listening");
+    info!(url = %ws_url(listen), "websocket_ready");
+    #[cfg(debug_assertions)]
+    info!(url = %http_url(listen), "swagger_ready");
+}
 
-    let addr = config.socket_addr()?;
+pub async fn start_server(config: Arc<ServerConfig>) -> Result<()> {
+    let state = Arc::new(AppState::new(config.clone())?);
+    let mut app = setup_metrics(&config, state.clone(), build_router(state.clone())).await?;
+    if let Some(admin) = admin_router(&config, state.clone()) {
+        info!("admin_api_enabled");
+        app = app.merge(admin);
+    }
+    let app = app.layer(middleware::from_fn_with_state(
+        state.clone(),
+        rate_limit::rate_limit,
+    ));
+
+    let shutdown_state = state.clone();
+    let cleanup_secs = config.rate_limit.cleanup_interval_secs;
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(cleanup_secs));
+        loop {
+            interval.tick().await;
+            let tracked = state.rate_limiter().retain_recent();
+            debug!(storage_size = tracked, "rate_limiter_storage");
+            metrics().rate_limiter_keys.set(tracked as i64);
+        }
+    });
+
+    let addr = socket_addr(&config.server)?;
 
     let listener = tokio::net::TcpListener::bind(addr)
         .await
         .with_context(|| format!("Failed to bind to {}", addr))?;
 
-    log_startup_info(&config);
+    log_startup_info(&config.server);
 
     axum::serve(
         listener,
         app.into_make_service_with_connect_info::<SocketAddr>(),
     )
-    .with_graceful_shutdown(shutdown_signal())
+    .with_graceful_shutdown(graceful_shutdown(shutdown_state, config.shutdown))
     .await
     .context("Server encountered an error during execution")?;
 
     info!("shutdown_complete");
     Ok(())
 }
-
-async fn shutdown_signal() {
-    let ctrl_c = async {
-        signal::ctrl_c()
-            .await
-            .expect("Failed to install Ctrl+C handler");
-    };
-    #[cfg(unix)]
-    let terminate = async {
-        signal::unix::signal(signal::unix::SignalKind::terminate())
-            .expect("Failed to install SIGTERM handler")
-            .recv()
-            .await;
-    };
-    #[cfg(not(unix))]
-    let terminate = std::future::pending::<()>();
-    tokio::select! {
-        _ = ctrl_c => {
-            info!(signal = "ctrl_c", "shutdown_signal_received");
-        },
-        _ = terminate => {
-            info!(signal = "sigterm", "shutdown_signal_received");
-        },
-    }
-}
@@ -0,0 +1,92 @@
+use crate::app::AppState;
+use std::{sync::Arc, time::Duration};
+use tokio::{signal, time::interval};
+use tracing::{info, warn};
+use ultimatexo_core::config::ShutdownSettings;
+
+/// RFC 6455 "going away": the server is shutting down.
+pub const GOING_AWAY: u16 = 1001;
+pub const SHUTDOWN_REASON: &str = "Server is shutting down";
+/// Time for send tasks to flush close frames.
+const FLUSH_DELAY: Duration = Duration::from_millis(500);
+
+/// Resolves once the server has been drained, at which point `axum::serve`
+/// stops. A second signal during the grace period skips the wait.
+pub async fn graceful_shutdown(state: Arc<AppState>, settings: ShutdownSettings) {
+    shutdown_signal().await;
+    state.set_draining(true);
+    let active_games = state.active_games().await;
+    info!(
+        grace_period_secs = settings.grace_period_secs,
+        active_games, "shutdown_started"
+    );
+
+    let mut ticker = interval(Duration::from_secs(1));
+    for elapsed in 0..=settings.grace_period_secs {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown_signal() => {
+                warn!("shutdown_forced");
+                break;
+            }
+        }
+        let active_games = state.active_games().await;
+        if active_games == 0 {
+            info!("shutdown_games_settled");
+            break;
+        }
+        let remaining_secs = settings.grace_period_secs - elapsed;
+        if remaining_secs == 0 {
+            info!(active_games, "shutdown_grace_period_elapsed");
+            break;
+        }
+        if elapsed % settings.warning_interval_secs == 0 {
+            let reached = state.warn_shutdown(remaining_secs).await;
+            info!(
+                remaining_secs,
+                reached, active_games, "shutdown_warning_sent"
+            );
+        }
+    }
+
+    let snapshots = state.snapshot_active_games().await;
+    if snapshots > 0 {
+        info!(snapshots, "games_snapshotted");
+    }
+    if !state.flush_events(settings.flush_timeout()).await {
+        warn!(
+            timeout_secs = settings.flush_timeout_secs,
+            "shutdown_event_flush_timed_out"
+        );
+    }
+    state
+        .close_all_connections(GOING_AWAY, SHUTDOWN_REASON)
+        .await;
+    tokio::time::sleep(FLUSH_DELAY).await;
+    info!("shutdown_drained");
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        signal::unix::signal(signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {
+            info!(signal = "ctrl_c", "shutdown_signal_received");
+        },
+        _ = terminate => {
+            info!(signal = "sigterm", "shutdown_signal_received");
+        },
+    }
+}
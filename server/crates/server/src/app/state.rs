@@ -1,48 +1,176 @@
+use crate::{app::RateLimiter, utils::otel::hash_ip};
 use anyhow::Result;
 
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, RwLock,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
 use ultimatexo_core::{
-    AppError, BotRoomRules, LocalRoomRules, RoomInfo, RoomType, StandardRoomRules,
+    AppError, BoardSize, BotConfig, BotLevel, BotRoomRules, GetRoomQuery, LobbyEvent,
+    LocalRoomRules, RoomDetail, RoomInfo, RoomOverview, RoomType, ServerConfig, ServerMessage,
+    StandardRoomRules,
+    config::{BotAvailability, BotLevelSettings},
 };
-use ultimatexo_services::RoomService;
+use ultimatexo_services::{EventBus, RoomService, WebhookConfig, metrics, spawn_webhooks};
 
 pub struct AppState {
+    config: Arc<ServerConfig>,
+    rate_limiter: RateLimiter,
+    bot_levels: RwLock<BotLevelSettings>,
+    draining: AtomicBool,
+    shutdown: CancellationToken,
     room_services: HashMap<RoomType, Arc<RoomService>>,
     room_metadata: Arc<tokio::sync::RwLock<HashMap<String, RoomType>>>,
+    events: EventBus,
 }
 
 impl AppState {
-    pub fn new() -> Self {
+    pub fn new(config: Arc<ServerConfig>) -> Result<Self> {
+        let events = EventBus::new();
+        if let Some(webhooks) = WebhookConfig::from_settings(&config.webhooks)? {
+            spawn_webhooks(&events, webhooks);
+        }
         let mut room_services = HashMap::new();
 
         room_services.insert(
             RoomType::Standard,
-            Arc::new(RoomService::with_rules(Arc::new(StandardRoomRules))),
+            Arc::new(RoomService::with_rules(
+                Arc::new(StandardRoomRules::new(config.rooms.cleanup_timeout())),
+                events.clone(),
+                config.bots,
+            )),
         );
 
         room_services.insert(
             RoomType::BotRoom,
-            Arc::new(RoomService::with_rules(Arc::new(BotRoomRules))),
+            Arc::new(RoomService::with_rules(
+                Arc::new(BotRoomRules),
+                events.clone(),
+                config.bots,
+            )),
         );
 
         room_services.insert(
             RoomType::LocalRoom,
-            Arc::new(RoomService::with_rules(Arc::new(LocalRoomRules))),
+            Arc::new(RoomService::with_rules(
+                Arc::new(LocalRoomRules),
+                events.clone(),
+                config.bots,
+            )),
         );
 
-        Self {
+        Ok(Self {
+            rate_limiter: RateLimiter::new(config.rate_limit.clone()),
+            bot_levels: RwLock::new(config.bot_levels.clone()),
+            draining: AtomicBool::new(false),
+            shutdown: CancellationToken::new(),
+            config,
             room_services,
             room_metadata: Arc::new(tokio::sync::RwLock::new(HashMap::new())),
+            events,
+        })
+    }
+
+    pub fn config(&self) -> &ServerConfig {
+        &self.config
+    }
+
+    pub fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+
+    pub fn bot_levels(&self) -> BotLevelSettings {
+        self.bot_levels
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    pub fn set_bot_level(
+        &self,
+        level: &BotLevel,
+        availability: BotAvailability,
+    ) -> BotLevelSettings {
+        let mut bot_levels = self.bot_levels.write().unwrap_or_else(|e| e.into_inner());
+        *bot_levels.get_mut(level) = availability;
+        bot_levels.clone()
+    }
+
+    /// The message to reject `room_info` with when its bot level is disabled.
+    pub fn bot_level_rejection(&self, room_info: &RoomInfo) -> Option<String> {
+        if room_info.room_type != RoomType::BotRoom {
+            return None;
         }
+        let level = match (&room_info.bot_level, &room_info.bot_config) {
+            (Some(level), _) => level.clone(),
+            // Out-of-range configs are left for `create_room` to report.
+            (None, Some(config)) if config.validate(&self.config.bots.limits).is_ok() => {
+                self.config.bots.level_for(config.difficulty())
+            }
+            _ => return None,
+        };
+        self.bot_levels
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .rejection(&level)
+    }
+
+    pub fn hash_ip(&self, ip: &str) -> String {
+        hash_ip(ip, self.config.privacy.ip_hash_salt.as_deref())
+    }
+
+    /// While draining, no rooms are created and only reconnections may join.
+    pub fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    pub fn set_draining(&self, draining: bool) {
+        self.draining.store(draining, Ordering::SeqCst);
     }
 
     pub async fn create_room(&self, room_info: RoomInfo) -> Result<String, AppError> {
-        if room_info.room_type == RoomType::BotRoom && room_info.bot_level.is_none() {
+        if self.is_draining() {
+            return Err(AppError::server_draining());
+        } else if room_info.room_type == RoomType::BotRoom
+            && room_info.bot_level.is_none()
+            && room_info.bot_config.is_none()
+        {
             return Err(AppError::missing_bot_level());
         } else if room_info.room_type != RoomType::BotRoom && room_info.bot_level.is_some() {
             return Err(AppError::invalid_bot_level());
+        } else if room_info.room_type != RoomType::BotRoom && room_info.bot_config.is_some() {
+            return Err(AppError::invalid_bot_config("none outside BotRoom"));
+        } else if room_info.bot_level.is_some() && room_info.bot_config.is_some() {
+            return Err(AppError::invalid_bot_config(
+                "either bot_level or bot_config, not both",
+            ));
+        } else if room_info.adaptive_difficulty
+            && matches!(room_info.bot_config, Some(BotConfig::Custom(_)))
+        {
+            return Err(AppError::invalid_bot_config(
+                "a Level when adaptive_difficulty is set",
+            ));
         } else if room_info.room_type == RoomType::LocalRoom && room_info.is_public {
             return Err(AppError::local_room_cannot_be_public());
+        } else if room_info.room_type == RoomType::Standard && room_info.position.is_some() {
+            return Err(AppError::invalid_position(
+                "no starting position outside BotRoom and LocalRoom",
+            ));
+        } else if room_info.rules.board_size != BoardSize::Three {
+            return Err(AppError::unsupported_board_size());
+        } else if !room_info.match_format.is_valid() {
+            return Err(AppError::invalid_match_format());
+        } else if room_info.adaptive_difficulty && room_info.room_type != RoomType::BotRoom {
+            return Err(AppError::adaptive_difficulty_requires_bot());
+        }
+        if let Some(config) = &room_info.bot_config {
+            config.validate(&self.config.bots.limits)?;
         }
         let room_type = room_info.room_type.clone();
         let service = self
@@ -74,16 +202,136 @@ impl AppState {
             .ok_or(AppError::unsupported_room_type())
     }
 
-    pub async fn get_public_rooms(&self, name_filter: Option<&str>) -> Vec<RoomInfo> {
+    pub async fn get_public_rooms(
+        &self,
+        query: &GetRoomQuery,
+    ) -> Result<(usize, Vec<RoomInfo>), AppError> {
+        Ok(self.standard_rooms()?.get_public_rooms(query).await)
+    }
+
+    pub fn subscribe_lobby(&self) -> Result<broadcast::Receiver<LobbyEvent>, AppError> {
+        Ok(self.standard_rooms()?.lobby().subscribe())
+    }
+
+    fn standard_rooms(&self) -> Result<&Arc<RoomService>, AppError> {
         self.room_services
             .get(&RoomType::Standard)
-            .unwrap()
-            .get_public_rooms(name_filter)
+            .ok_or_else(AppError::unsupported_room_type)
+    }
+
+    /// Refreshes the gauges that are cheaper to read at scrape time than to
+    /// keep in sync on every join and leave.
+    pub fn refresh_metrics(&self) {
+        for (room_type, service) in &self.room_services {
+            metrics().set_room_occupancy(
+                room_type,
+                service.room_count(),
+                service.connected_players(),
+            );
+        }
+    }
+
+    pub async fn rooms_overview(&self) -> Vec<RoomOverview> {
+        let mut rooms = Vec::new();
+        for service in self.room_services.values() {
+            rooms.extend(service.rooms_overview().await);
+        }
+        rooms.sort_by_key(|room| std::cmp::Reverse(room.info.created_at));
+        rooms
+    }
+
+    pub async fn room_detail(&self, room_id: &str) -> Result<RoomDetail, AppError> {
+        self.get_room_service(room_id)
+            .await?
+            .room_detail(room_id)
+            .await
+    }
+
+    pub async fn close_room(&self, room_id: &str, reason: &str) -> Result<(), AppError> {
+        self.get_room_service(room_id)
+            .await?
+            .close_room(room_id, reason)
+            .await
+    }
+
+    pub async fn kick_player(
+        &self,
+        room_id: &str,
+        player_id: &str,
+        reason: &str,
+    ) -> Result<(), AppError> {
+        self.get_room_service(room_id)
+            .await?
+            .kick_player(room_id, player_id, reason)
+            .await
+    }
+
+    /// Returns how many player sockets were reached; lobby watchers are not
+    /// counted.
+    pub async fn announce(&self, message: &str) -> usize {
+        let mut reached = 0;
+        for service in self.room_services.values() {
+            reached += service.announce(message).await;
+        }
+        reached
+    }
+
+    pub async fn warn_shutdown(&self, remaining_secs: u64) -> usize {
+        let mut reached = 0;
+        for service in self.room_services.values() {
+            reached += service.warn_shutdown(remaining_secs).await;
+        }
+        reached
+    }
+
+    pub async fn active_games(&self) -> usize {
+        let mut active = 0;
+        for service in self.room_services.values() {
+            active += service.active_games().await;
+        }
+        active
+    }
+
+    pub async fn snapshot_active_games(&self) -> usize {
+        let mut snapshots = 0;
+        for service in self.room_services.values() {
+            snapshots += service.snapshot_active_games().await;
+        }
+        snapshots
+    }
+
+    /// Waits for event subscribers to finish with what has been published.
+    /// Returns false if `limit` ran out first.
+    pub async fn flush_events(&self, limit: Duration) -> bool {
+        self.events.flush(limit).await
+    }
+
+    /// Cancelled once every socket has been told to close for shutdown.
+    pub fn shutdown_token(&self) -> &CancellationToken {
+        &self.shutdown
+    }
+
+    pub async fn close_all_connections(&self, code: u16, reason: &str) {
+        for service in self.room_services.values() {
+            service.broadcast(ServerMessage::close(code, reason)).await;
+        }
+        self.shutdown.cancel();
+    }
+
+    pub fn room_count(&self) -> usize {
+        self.room_services.values().map(|s| s.room_count()).sum()
+    }
+
+    pub fn connected_players(&self) -> usize {
+        self.room_services
+            .values()
+            .map(|s| s.connected_players())
+            .sum()
     }
 
     pub async fn get_room_info(&self, room_id: &str) -> Option<RoomInfo> {
         for service in self.room_services.values() {
-            if let Ok(info) = service.get_room_info(room_id) {
+            if let Ok(info) = service.get_room_info(room_id).await {
                 return Some(info);
             }
         }
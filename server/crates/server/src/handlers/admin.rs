@@ -0,0 +1,200 @@
+use crate::{app::AppState, utils::auth::bearer_token_matches};
+use axum::{
+    Json,
+    extract::{Path, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::{collections::BTreeMap, sync::Arc};
+use tracing::info;
+use ultimatexo_core::{
+    AppError, BotLevel, RoomDetail, RoomOverview,
+    config::{BotAvailability, RateLimitPolicy, RateLimitRoute},
+};
+
+/// Rejects every `/admin` request that does not carry `admin.token`.
+pub async fn require_admin(
+    State(state): State<Arc<AppState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let authorized = state
+        .config()
+        .admin
+        .token
+        .as_deref()
+        .is_some_and(|expected| bearer_token_matches(request.headers(), expected));
+    if !authorized {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    next.run(request).await
+}
+
+pub async fn get_policies(State(state): State<Arc<AppState>>) -> Json<Value> {
+    Json(json!({
+        "rate_limit": state.rate_limiter().settings(),
+        "bot_levels": state.bot_levels(),
+    }))
+}
+
+/// Fields left out keep their current value; `routes` replaces the whole
+/// override table when present.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimitUpdate {
+    pub enabled: Option<bool>,
+    pub default: Option<RateLimitPolicy>,
+    pub routes: Option<BTreeMap<RateLimitRoute, RateLimitPolicy>>,
+}
+
+pub async fn update_rate_limits(
+    State(state): State<Arc<AppState>>,
+    Json(update): Json<RateLimitUpdate>,
+) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
+    let mut settings = state.rate_limiter().settings();
+    if let Some(enabled) = update.enabled {
+        settings.enabled = enabled;
+    }
+    if let Some(default) = update.default {
+        settings.default = default;
+    }
+    if let Some(routes) = update.routes {
+        settings.routes = routes;
+    }
+    settings.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "message": e.to_string() })),
+        )
+    })?;
+
+    info!(
+        enabled = settings.enabled,
+        default = ?settings.default,
+        routes = ?settings.routes,
+        "rate_limits_updated"
+    );
+    state.rate_limiter().update(settings.clone());
+    Ok(Json(json!(settings)))
+}
+
+pub async fn update_bot_level(
+    State(state): State<Arc<AppState>>,
+    Path(level): Path<BotLevel>,
+    Json(availability): Json<BotAvailability>,
+) -> Json<Value> {
+    info!(
+        bot_level = level.as_str(),
+        enabled = availability.enabled,
+        disabled_message = ?availability.message,
+        "bot_level_updated"
+    );
+    Json(json!(state.set_bot_level(&level, availability)))
+}
+
+fn admin_error(error: AppError) -> (StatusCode, Json<Value>) {
+    (error.status_code(), Json(json!(error.to_payload())))
+}
+
+pub async fn list_rooms(State(state): State<Arc<AppState>>) -> Json<Vec<RoomOverview>> {
+    Json(state.rooms_overview().await)
+}
+
+pub async fn get_room_detail(
+    State(state): State<Arc<AppState>>,
+    Path(room_id): Path<String>,
+) -> Result<Json<RoomDetail>, (StatusCode, Json<Value>)> {
+    state
+        .room_detail(&room_id)
+        .await
+        .map(Json)
+        .map_err(admin_error)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReasonPayload {
+    pub reason: String,
+}
+
+pub async fn close_room(
+    State(state): State<Arc<AppState>>,
+    Path(room_id): Path<String>,
+    Json(payload): Json<ReasonPayload>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    state
+        .close_room(&room_id, &payload.reason)
+        .await
+        .map_err(admin_error)?;
+    info!(room_id = %room_id, reason = %payload.reason, "room_force_closed");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn kick_player(
+    State(state): State<Arc<AppState>>,
+    Path((room_id, player_id)): Path<(String, String)>,
+    Json(payload): Json<ReasonPayload>,
+) -> Result<StatusCode, (StatusCode, Json<Value>)> {
+    state
+        .kick_player(&room_id, &player_id, &payload.reason)
+        .await
+        .map_err(admin_error)?;
+    info!(
+        room_id = %room_id,
+        player_id = %player_id,
+        reason = %payload.reason,
+        "player_kicked"
+    );
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AnnouncementPayload {
+    pub message: String,
+}
+
+pub async fn announce(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<AnnouncementPayload>,
+) -> Json<Value> {
+    let reached = state.announce(&payload.message).await;
+    info!(reached, "announcement_sent");
+    Json(json!({ "reached": reached }))
+}
+
+fn drain_status(state: &AppState) -> Json<Value> {
+    Json(json!({
+        "draining": state.is_draining(),
+        "rooms": state.room_count(),
+        "connected_players": state.connected_players(),
+    }))
+}
+
+pub async fn get_drain(State(state): State<Arc<AppState>>) -> Json<Value> {
+    drain_status(&state)
+}
+
+/// Turning draining on stops new rooms and new joins while games in progress
+/// carry on; `message`, when given, is announced to everyone.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DrainPayload {
+    pub draining: bool,
+    pub message: Option<String>,
+}
+
+pub async fn set_drain(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<DrainPayload>,
+) -> Json<Value> {
+    state.set_draining(payload.draining);
+    info!(draining = payload.draining, "drain_updated");
+    if let Some(message) = &payload.message {
+        state.announce(message).await;
+    }
+    drain_status(&state)
+}
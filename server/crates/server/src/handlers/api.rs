@@ -1,7 +1,4 @@
-use crate::{
-    app::AppState,
-    utils::{otel::hash_ip, real_ip::real_client_ip},
-};
+use crate::{app::AppState, utils::real_ip::real_client_ip};
 use axum::{
     Json,
     extract::{ConnectInfo, Path, Query, State},
@@ -10,25 +7,28 @@ use axum::{
 use serde_json::{Value, json};
 use std::{net::SocketAddr, sync::Arc};
 use tracing::{info, warn};
-use ultimatexo_core::{BotLevel, GetRoomQuery, RoomInfo, RoomType};
+use ultimatexo_core::{AppError, GetRoomQuery, RoomInfo};
 
 #[utoipa::path(
     get,
     path = "/rooms",
-    params(
-        ("name" = Option<String>, Query, description = "Optional room name filter")
-    ),
+    params(GetRoomQuery),
     responses(
-        (status = 200, description = "List of rooms retrieved successfully", body = Vec<RoomInfo>),
+        (status = 200, description = "List of rooms retrieved successfully", body = Vec<RoomInfo>,
+            headers(("X-Total-Count" = usize, description = "Number of matching rooms before pagination"))),
     ),
     tag = "rooms"
 )]
 pub async fn get_rooms(
     State(state): State<Arc<AppState>>,
-    Query(GetRoomQuery { name }): Query<GetRoomQuery>,
+    Query(query): Query<GetRoomQuery>,
 ) -> Result<(HeaderMap, Json<Vec<RoomInfo>>), StatusCode> {
-    let rooms = state.get_public_rooms(name.as_deref()).await;
+    let (total, rooms) = state
+        .get_public_rooms(&query)
+        .await
+        .map_err(|e| e.status_code())?;
     let mut headers = HeaderMap::new();
+    headers.insert("X-Total-Count", HeaderValue::from(total));
     headers.insert(
         "Cache-Control",
         HeaderValue::from_static("no-store, no-cache, must-revalidate"),
@@ -78,17 +78,10 @@ pub async fn create_room(
     Json(room_info): Json<RoomInfo>,
 ) -> Result<Json<Value>, (StatusCode, Json<Value>)> {
     let client_ip = real_client_ip(&headers, addr);
-    let client_hash = hash_ip(&client_ip);
+    let client_hash = state.hash_ip(&client_ip);
 
-    if room_info.room_type == RoomType::BotRoom
-        && let Some(BotLevel::Expert) = room_info.bot_level
-    {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "message": "Expert bot is currently disabled."
-            })),
-        ));
+    if let Some(message) = state.bot_level_rejection(&room_info) {
+        return Err((StatusCode::BAD_REQUEST, Json(json!({ "message": message }))));
     }
 
     match state.create_room(room_info).await {
@@ -100,6 +93,9 @@ pub async fn create_room(
             );
             Ok(Json(json!({ "room_id": room_id })))
         }
+        Err(e @ (AppError::Unavailable { .. } | AppError::Validation(_))) => {
+            Err((e.status_code(), Json(json!({ "message": e.to_string() }))))
+        }
         Err(_) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({ "message": "Failed to create room. Please try again." })),
@@ -121,6 +117,7 @@ const MAX_ERROR_URL_LEN: usize = 2048;
 pub async fn client_error(
     headers: HeaderMap,
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    State(state): State<Arc<AppState>>,
     Json(payload): Json<ClientErrorPayload>,
 ) -> Result<StatusCode, (StatusCode, Json<Value>)> {
     if payload.message.len() > MAX_ERROR_MESSAGE_LEN {
@@ -147,7 +144,7 @@ pub async fn client_error(
     }
 
     let client_ip = real_client_ip(&headers, addr);
-    let client_hash = hash_ip(&client_ip);
+    let client_hash = state.hash_ip(&client_ip);
     warn!(
         client_hash = %client_hash,
         error_message = %payload.message,
@@ -162,16 +159,25 @@ pub async fn client_error(
     get,
     path = "/health",
     responses(
-        (status = 200, description = "Server is healthy", body = inline(Object))
+        (status = 200, description = "Server is healthy", body = inline(Object)),
+        (status = 503, description = "Server is draining and should not receive new traffic", body = inline(Object))
     ),
     tag = "system"
 )]
-pub async fn health_check() -> Result<Json<Value>, StatusCode> {
-    Ok(Json(json!({
-        "status": "healthy",
-        "timestamp": std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs(),
-    })))
+pub async fn health_check(State(state): State<Arc<AppState>>) -> (StatusCode, Json<Value>) {
+    let (status, label) = if state.is_draining() {
+        (StatusCode::SERVICE_UNAVAILABLE, "draining")
+    } else {
+        (StatusCode::OK, "healthy")
+    };
+    (
+        status,
+        Json(json!({
+            "status": label,
+            "timestamp": std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        })),
+    )
 }
@@ -2,17 +2,21 @@ use utoipa::OpenApi;
 
 use crate::handlers::{
     api::{__path_create_room, __path_get_room, __path_get_rooms, __path_health_check},
+    lobby::__path_lobby_websocket_handler,
     websocket::__path_websocket_handler,
 };
 use ultimatexo_core::{
-    Action, Board, ClientMessage, GetRoomQuery, PlayerAction, RoomInfo, SerizlizedPlayer,
-    ServerMessage, WebSocketQuery,
+    Action, Board, BoardSize, BotConfig, BotEngine, BotStrength, Capability, ClientMessage,
+    CustomBot, Encoding, FirstMover, GetRoomQuery, LobbyEvent, MarkerPreference, MatchFormat,
+    PlayerAction, RoomInfo, RoomSort, RuleSet, SerizlizedPlayer, ServerMessage, WebSocketQuery,
+    error::{ErrorCode, ErrorDetails, ErrorPayload},
 };
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         websocket_handler,
+        lobby_websocket_handler,
         get_rooms,
         get_room,
         create_room,
@@ -29,6 +33,22 @@ use ultimatexo_core::{
             RoomInfo,
             GetRoomQuery,
             Board,
+            Capability,
+            Encoding,
+            ErrorPayload,
+            ErrorCode,
+            ErrorDetails,
+            LobbyEvent,
+            RoomSort,
+            RuleSet,
+            BoardSize,
+            MarkerPreference,
+            FirstMover,
+            MatchFormat,
+            BotStrength,
+            BotConfig,
+            CustomBot,
+            BotEngine,
         )
     ),
     tags(
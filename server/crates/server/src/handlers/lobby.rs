@@ -0,0 +1,112 @@
+use crate::app::{
+    AppState,
+    shutdown::{GOING_AWAY, SHUTDOWN_REASON},
+};
+use axum::{
+    extract::{
+        Query, State, WebSocketUpgrade,
+        ws::{CloseFrame, Message, WebSocket},
+    },
+    response::Response,
+};
+use futures_util::{SinkExt, StreamExt};
+use std::sync::Arc;
+use tokio::{select, sync::broadcast::error::RecvError};
+use tracing::{debug, warn};
+use ultimatexo_core::{AppError, GetRoomQuery, LobbyEvent};
+
+#[utoipa::path(
+    get,
+    path = "/lobby",
+    params(GetRoomQuery),
+    responses(
+        (status = 101, description = "Streams LobbyEvent frames, starting with a Snapshot", body = LobbyEvent),
+    ),
+    tag = "websocket"
+)]
+pub async fn lobby_websocket_handler(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<GetRoomQuery>,
+) -> Response {
+    ws.on_upgrade(move |socket| handle_lobby_socket(socket, state, query))
+}
+
+#[tracing::instrument(skip(socket, state))]
+async fn handle_lobby_socket(socket: WebSocket, state: Arc<AppState>, query: GetRoomQuery) {
+    let (mut sender, mut receiver) = socket.split();
+    let mut events = match state.subscribe_lobby() {
+        Ok(events) => events,
+        Err(e) => {
+            warn!(error = %e, "lobby_subscribe_failed");
+            return;
+        }
+    };
+
+    if let Err(e) = send_snapshot(&mut sender, &state, &query).await {
+        warn!(error = %e, "lobby_snapshot_failed");
+        return;
+    }
+
+    loop {
+        select! {
+            event = events.recv() => {
+                let result = match event {
+                    Ok(event) => match event.for_query(&query) {
+                        Some(event) => send_event(&mut sender, &event).await,
+                        None => Ok(()),
+                    },
+                    Err(RecvError::Lagged(skipped)) => {
+                        debug!(skipped = skipped, "lobby_feed_lagged");
+                        send_snapshot(&mut sender, &state, &query).await
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if let Err(e) = result {
+                    warn!(error = %e, "lobby_send_failed");
+                    break;
+                }
+            }
+            _ = state.shutdown_token().cancelled() => {
+                let frame = CloseFrame {
+                    code: GOING_AWAY,
+                    reason: SHUTDOWN_REASON.into(),
+                };
+                let _ = sender.send(Message::Close(Some(frame))).await;
+                break;
+            }
+            message = receiver.next() => {
+                match message {
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => {}
+                    Some(Err(e)) => {
+                        warn!(error = %e, "lobby_websocket_error");
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    debug!("lobby_disconnected");
+}
+
+async fn send_snapshot<S>(
+    sender: &mut S,
+    state: &AppState,
+    query: &GetRoomQuery,
+) -> Result<(), AppError>
+where
+    S: SinkExt<Message, Error = axum::Error> + Unpin,
+{
+    let (_, rooms) = state.get_public_rooms(query).await?;
+    send_event(sender, &LobbyEvent::Snapshot { rooms }).await
+}
+
+async fn send_event<S>(sender: &mut S, event: &LobbyEvent) -> Result<(), AppError>
+where
+    S: SinkExt<Message, Error = axum::Error> + Unpin,
+{
+    let json = serde_json::to_string(event)?;
+    sender.send(Message::Text(json.into())).await?;
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use crate::{app::AppState, utils::auth::bearer_token_matches};
+use axum::{
+    Extension,
+    extract::State,
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+use ultimatexo_services::metrics;
+
+/// Bearer token required by `/metrics`; `None` leaves the route open, which
+/// is only done when it is served on its own private bind address.
+#[derive(Clone, Default)]
+pub struct MetricsToken(pub Option<Arc<str>>);
+
+pub async fn metrics_handler(
+    State(state): State<Arc<AppState>>,
+    Extension(token): Extension<MetricsToken>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(expected) = token.0.as_deref()
+        && !bearer_token_matches(&headers, expected)
+    {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    state.refresh_metrics();
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        metrics().render(),
+    )
+        .into_response()
+}
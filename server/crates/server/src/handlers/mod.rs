@@ -1,13 +1,20 @@
+mod admin;
 mod api;
 #[cfg(debug_assertions)]
 mod doc;
+mod lobby;
+mod metrics;
 mod tasks;
 mod websocket;
 
+pub use admin::{
+    announce, close_room, get_drain, get_policies, get_room_detail, kick_player, list_rooms,
+    require_admin, set_drain, update_bot_level, update_rate_limits,
+};
 pub use api::{client_error, create_room, get_room, get_rooms, health_check};
 #[cfg(debug_assertions)]
 pub use doc::ApiDoc;
-#[cfg(not(debug_assertions))]
-pub use tasks::spawn_heartbeat_task;
-pub use tasks::{ConnectionContext, spawn_receive_task, spawn_send_task};
+pub use lobby::lobby_websocket_handler;
+pub use metrics::{MetricsToken, metrics_handler};
+pub use tasks::{ConnectionContext, spawn_heartbeat_task, spawn_receive_task, spawn_send_task};
 pub use websocket::{Sender, websocket_handler};
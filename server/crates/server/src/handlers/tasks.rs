@@ -4,61 +4,60 @@ use anyhow::Result;
 use axum::extract::ws::{Message, WebSocket};
 use futures_util::{SinkExt, StreamExt, stream::SplitStream};
 use std::sync::Arc;
-#[cfg(not(debug_assertions))]
-use tokio::sync::RwLock;
-#[cfg(not(debug_assertions))]
-use tokio::time::{Instant, interval};
 use tokio::{
-    sync::mpsc::{UnboundedReceiver, UnboundedSender},
+    sync::{
+        RwLock,
+        mpsc::{UnboundedReceiver, UnboundedSender},
+    },
     task::JoinHandle,
+    time::{Instant, interval},
 };
 use tracing::{Instrument, debug, warn};
-use ultimatexo_core::{AppError, ClientMessage, Room, ServerMessage};
+use ultimatexo_core::{
+    AppError, ClientMessage, Encoding, ProtocolVersion, Room, ServerMessage,
+    config::WebSocketSettings,
+};
+use ultimatexo_services::metrics;
 
 #[derive(Debug)]
 pub struct ConnectionContext {
     pub player_id: String,
     pub player_tx: UnboundedSender<ServerMessage>,
-    #[cfg(not(debug_assertions))]
+    pub protocol: ProtocolVersion,
+    pub encoding: Encoding,
     pub last_pong: Arc<RwLock<Instant>>,
 }
 
 impl ConnectionContext {
-    pub fn new(player_id: String, player_tx: UnboundedSender<ServerMessage>) -> Self {
+    pub fn new(
+        player_id: String,
+        player_tx: UnboundedSender<ServerMessage>,
+        protocol: ProtocolVersion,
+        encoding: Encoding,
+    ) -> Self {
         Self {
             player_id,
             player_tx,
-            #[cfg(not(debug_assertions))]
+            protocol,
+            encoding,
             last_pong: Arc::new(RwLock::new(Instant::now())),
         }
     }
 }
 
-#[cfg(not(debug_assertions))]
-pub fn spawn_heartbeat_task(ctx: Arc<ConnectionContext>) -> JoinHandle<()> {
-    use std::env;
-    use std::time::Duration;
-
+pub fn spawn_heartbeat_task(
+    ctx: Arc<ConnectionContext>,
+    settings: WebSocketSettings,
+) -> JoinHandle<()> {
     tokio::spawn(
         async move {
-            let ping_interval_secs: u64 = env::var("WEBSOCKET_PING_INTERVAL_SECS")
-                .unwrap_or_else(|_| "3".to_string())
-                .parse()
-                .unwrap_or(3);
-            let mut interval = interval(Duration::from_secs(ping_interval_secs));
+            let mut interval = interval(settings.ping_interval());
 
             loop {
                 interval.tick().await;
 
                 let last_pong_time = *ctx.last_pong.read().await;
-                if last_pong_time.elapsed()
-                    > Duration::from_secs(
-                        env::var("WEBSOCKET_PONG_TIMEOUT_SECS")
-                            .unwrap_or_else(|_| "10".to_string())
-                            .parse()
-                            .unwrap_or(10),
-                    )
-                {
+                if last_pong_time.elapsed() > settings.pong_timeout() {
                     warn!(player_id = %ctx.player_id, "player_timeout");
                     break;
                 }
@@ -84,7 +83,15 @@ pub fn spawn_send_task(
     tokio::spawn(
         async move {
             while let Some(server_message) = message_receiver.recv().await {
-                match handle_outgoing_message(server_message, sender.clone()).await {
+                if !ctx.protocol.can_send(&server_message) {
+                    debug!(
+                        player_id = %ctx.player_id,
+                        protocol = ctx.protocol.number(),
+                        "message_skipped_for_protocol"
+                    );
+                    continue;
+                }
+                match handle_outgoing_message(server_message, sender.clone(), ctx.encoding).await {
                     Ok(should_continue) => {
                         if !should_continue {
                             break;
@@ -131,16 +138,15 @@ pub fn spawn_receive_task(
                                 error = %e,
                                 "message_handle_failed"
                             );
-                            let _ = ctx.player_tx.send(ServerMessage::Error(e));
+                            let _ = ctx.player_tx.send(ServerMessage::error(&e, ctx.protocol));
                         }
                     }
                     Err(e) => {
                         warn!(player_id = %ctx.player_id, error = %e, "websocket_error");
+                        let error = AppError::internal_error(e.to_string());
                         let _ = ctx
                             .player_tx
-                            .send(ServerMessage::Error(AppError::internal_error(
-                                e.to_string(),
-                            )));
+                            .send(ServerMessage::error(&error, ctx.protocol));
                         break;
                     }
                 }
@@ -150,20 +156,40 @@ pub fn spawn_receive_task(
     )
 }
 
-async fn handle_outgoing_message(server_message: ServerMessage, sender: Sender) -> Result<bool> {
-    if let ServerMessage::WebsocketMessage(Message::Close(_)) = server_message {
+async fn handle_outgoing_message(
+    server_message: ServerMessage,
+    sender: Sender,
+    encoding: Encoding,
+) -> Result<bool> {
+    if let ServerMessage::WebsocketMessage(Message::Close(frame)) = server_message {
+        if let Some(frame) = frame
+            && let Err(e) = sender.lock().await.send(Message::Close(Some(frame))).await
+        {
+            debug!(error = %e, "websocket_close_frame_failed");
+        }
         return Ok(false);
     }
-    let json_message = server_message.to_json().map_err(|e| {
-        AppError::internal_error(format!("Failed to serialize server message: {}", e))
-    })?;
-    if let Err(e) = sender
-        .lock()
-        .await
-        .send(Message::Text(json_message.into()))
-        .await
-    {
+    let message = match encoding {
+        Encoding::Json => Message::Text(
+            server_message
+                .to_json()
+                .map_err(|e| {
+                    AppError::internal_error(format!("Failed to serialize server message: {}", e))
+                })?
+                .into(),
+        ),
+        Encoding::MessagePack => Message::Binary(
+            server_message
+                .to_msgpack()
+                .map_err(|e| {
+                    AppError::internal_error(format!("Failed to serialize server message: {}", e))
+                })?
+                .into(),
+        ),
+    };
+    if let Err(e) = sender.lock().await.send(message).await {
         warn!(error = %e, "websocket_send_failed");
+        metrics().ws_send_failures.inc();
         return Err(
             AppError::internal_error("Failed to send message to WebSocket".to_string()).into(),
         );
@@ -182,6 +208,9 @@ async fn handle_incoming_message(
     let client_message: ClientMessage = match message {
         Message::Text(text) => serde_json::from_str(&text)
             .map_err(|e| AppError::internal_error(format!("Invalid JSON message: {}", e)))?,
+        Message::Binary(bytes) if ctx.encoding == Encoding::MessagePack => {
+            rmp_serde::from_slice(&bytes)?
+        }
         _ => {
             return Err(AppError::internal_error(
                 "This messages not supported".to_string(),
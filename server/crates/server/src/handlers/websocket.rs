@@ -1,12 +1,12 @@
 use crate::{
     app::AppState,
-    handlers::{ConnectionContext, spawn_receive_task, spawn_send_task},
-    utils::{otel::hash_ip, real_ip::real_client_ip},
+    handlers::{ConnectionContext, spawn_heartbeat_task, spawn_receive_task, spawn_send_task},
+    utils::real_ip::real_client_ip,
 };
 use axum::{
     extract::{
         ConnectInfo, Path, Query, State, WebSocketUpgrade,
-        ws::{Message, WebSocket},
+        ws::{CloseFrame, Message, WebSocket},
     },
     http::HeaderMap,
     response::Response,
@@ -19,7 +19,8 @@ use std::{net::SocketAddr, sync::Arc};
 use tokio::{select, sync::Mutex};
 use tracing::{debug, error, info, warn};
 use ultimatexo_core::{
-    AppError, PlayerAction, Room, RoomType, SerizlizedPlayer, ServerMessage, Status, WebSocketQuery,
+    AppError, Capability, PlayerAction, ProtocolVersion, Room, RoomType, SerizlizedPlayer,
+    ServerMessage, WebSocketQuery,
 };
 use ultimatexo_services::GameAIService;
 
@@ -42,44 +43,49 @@ pub async fn websocket_handler(
     ConnectInfo(addr): ConnectInfo<SocketAddr>,
     headers: HeaderMap,
 ) -> Response {
-    let client_ip = real_client_ip(&headers, addr);
-    ws.on_upgrade(move |socket| handle_socket_upgrade(socket, state, room_id, payload, client_ip))
+    let client_hash = state.hash_ip(&real_client_ip(&headers, addr));
+    ws.on_upgrade(move |socket| handle_socket_upgrade(socket, state, room_id, payload, client_hash))
 }
 
-#[tracing::instrument(skip(socket, state, payload, client_ip), fields(room_id = %room_id))]
+#[tracing::instrument(skip(socket, state, payload, client_hash), fields(room_id = %room_id))]
 async fn handle_socket_upgrade(
     socket: WebSocket,
     state: Arc<AppState>,
     room_id: String,
     payload: WebSocketQuery,
-    client_ip: String,
+    client_hash: String,
 ) {
     let (sender, receiver) = socket.split();
     let sender = Arc::new(Mutex::new(sender));
 
+    // A version the server does not know is newer than it, so it is answered
+    // in the latest shape.
+    let protocol = ProtocolVersion::negotiate(payload.version).unwrap_or(ProtocolVersion::LATEST);
     if let Err(error) = handle_socket(
         sender.clone(),
         receiver,
         state,
         room_id.clone(),
         payload,
-        client_ip.clone(),
+        client_hash.clone(),
     )
     .await
-        && let Err(e) = send_error_and_close(sender, error, &client_ip).await
+        && let Err(e) = send_error_and_close(sender, error, protocol, &client_hash).await
     {
         warn!(room_id = %room_id, error = %e, "send_initial_error_failed");
     }
 }
 
 #[tracing::instrument(
-    skip(sender, receiver, state, payload, client_ip),
+    skip(sender, receiver, state, payload, client_hash),
     fields(
         room_id = %room_id,
         player_id = tracing::field::Empty,
         client_hash = tracing::field::Empty,
         room_type = tracing::field::Empty,
         is_reconnecting = %payload.is_reconnecting,
+        protocol = ?payload.version,
+        encoding = ?payload.encoding,
     )
 )]
 async fn handle_socket(
@@ -88,11 +94,15 @@ async fn handle_socket(
     state: Arc<AppState>,
     room_id: String,
     payload: WebSocketQuery,
-    client_ip: String,
+    client_hash: String,
 ) -> Result<(), AppError> {
-    let client_hash = hash_ip(&client_ip);
     info!(client_hash = %client_hash, room_id = %room_id, "user_connecting");
 
+    let protocol = ProtocolVersion::negotiate(payload.version)?;
+    let encoding = protocol.negotiate_encoding(payload.encoding)?;
+    if state.is_draining() && !payload.is_reconnecting {
+        return Err(AppError::server_draining());
+    }
     let room_service = state.get_room_service(&room_id).await?;
     let is_reconnecting = payload.is_reconnecting;
     let (room, player_id) = room_service
@@ -106,50 +116,42 @@ async fn handle_socket(
 
     let (player_tx, player_rx) = tokio::sync::mpsc::unbounded_channel();
 
-    let connection_ctx = Arc::new(ConnectionContext::new(player_id.clone(), player_tx));
+    let connection_ctx = Arc::new(ConnectionContext::new(
+        player_id.clone(),
+        player_tx,
+        protocol,
+        encoding,
+    ));
+    if connection_ctx
+        .player_tx
+        .send(ServerMessage::hello(protocol, encoding))
+        .is_err()
+    {
+        warn!(player_id = %player_id, "send_hello_failed");
+    }
 
     handle_game_start(room.clone(), connection_ctx.clone(), is_reconnecting).await?;
 
     let mut send_task = spawn_send_task(sender, player_rx, connection_ctx.clone());
     let mut receive_task = spawn_receive_task(receiver, room.clone(), connection_ctx.clone());
 
-    let result = {
-        #[cfg(debug_assertions)]
-        {
-            select! {
-                r = &mut send_task => {
-                    receive_task.abort();
-                    ("send", r)
-                },
-                r = &mut receive_task => {
-                    send_task.abort();
-                    ("receive", r)
-                },
-            }
-        }
-        #[cfg(not(debug_assertions))]
-        {
-            use crate::handlers::spawn_heartbeat_task;
-
-            let mut heartbeat_task = spawn_heartbeat_task(connection_ctx.clone());
-            select! {
-                r = &mut heartbeat_task => {
-                    send_task.abort();
-                    receive_task.abort();
-                    ("heartbeat", r)
-                },
-                r = &mut send_task => {
-                    heartbeat_task.abort();
-                    receive_task.abort();
-                    ("send", r)
-                },
-                r = &mut receive_task => {
-                    heartbeat_task.abort();
-                    send_task.abort();
-                    ("receive", r)
-                },
-            }
-        }
+    let mut heartbeat_task = spawn_heartbeat_task(connection_ctx.clone(), state.config().websocket);
+    let result = select! {
+        r = &mut heartbeat_task => {
+            send_task.abort();
+            receive_task.abort();
+            ("heartbeat", r)
+        },
+        r = &mut send_task => {
+            heartbeat_task.abort();
+            receive_task.abort();
+            ("send", r)
+        },
+        r = &mut receive_task => {
+            heartbeat_task.abort();
+            send_task.abort();
+            ("receive", r)
+        },
     };
 
     match result {
@@ -175,32 +177,31 @@ async fn handle_game_start(
     handle_player_connection_message(room.clone(), ctx, is_reconnecting).await?;
 
     let player_count = room.get_player_count();
-    let game_status = room.game.lock().await.get_board_status();
     match room.info.room_type {
         RoomType::Standard => {
-            if player_count == 2
-                && matches!(game_status, Status::WaitingForPlayers | Status::Paused)
-            {
-                room.game.lock().await.set_board_status(Status::InProgress);
+            if player_count == 2 && room.start_or_resume().await?.is_some() {
                 room.send_board().await;
             }
         }
         RoomType::BotRoom => {
-            let player_marker = room.get_player(player_id).await?.info.marker;
-            let current_player = room.game.lock().await.get_current_player().marker;
-            room.game.lock().await.set_board_status(Status::InProgress);
-            if current_player != player_marker {
-                let mut game = room.game.lock().await;
-                if is_reconnecting {
-                    GameAIService::make_ai_move(&mut game, current_player).await?;
-                } else {
-                    GameAIService::make_random_ai_move(&mut game).await?;
+            if room.start_or_resume().await?.is_some() {
+                let player_marker = room.get_player(player_id).await?.info.marker;
+                let current_player = room.game.lock().await.get_current_player().marker;
+                if current_player != player_marker
+                    && let Some(bot_move) = GameAIService::make_ai_move(
+                        &room.game,
+                        current_player,
+                        room.info.bot_level.as_ref(),
+                    )
+                    .await?
+                {
+                    room.publish_move(None, current_player, bot_move).await;
                 }
             }
             room.send_board().await;
         }
         RoomType::LocalRoom => {
-            room.game.lock().await.set_board_status(Status::InProgress);
+            room.start_or_resume().await?;
             room.send_board().await;
         }
     }
@@ -251,20 +252,32 @@ async fn handle_player_connection_message(
 async fn send_error_and_close(
     sender: Sender,
     error: AppError,
-    client_ip: &str,
+    protocol: ProtocolVersion,
+    client_hash: &str,
 ) -> Result<(), AppError> {
-    let client_hash = hash_ip(client_ip);
     info!(
         client_hash = %client_hash,
         error = %error,
         "send_error_and_close"
     );
+    // V1 clients were sent the error as text and a bare close frame.
+    let (error_text, close_frame) = if protocol.supports(Capability::StructuredErrors) {
+        let close_frame = CloseFrame {
+            code: error.close_code(),
+            reason: error.code().as_str().into(),
+        };
+        (
+            ServerMessage::error(&error, protocol).to_json()?,
+            Some(close_frame),
+        )
+    } else {
+        (format!("Error: {}", error), None)
+    };
     let mut sender = sender.lock().await;
-    let error_msg = Message::Text(format!("Error: {}", error).into());
-    if let Err(e) = sender.send(error_msg).await {
+    if let Err(e) = sender.send(Message::Text(error_text.into())).await {
         warn!(client_hash = %client_hash, error = %e, "send_error_message_failed");
     }
-    if let Err(e) = sender.send(Message::Close(None)).await {
+    if let Err(e) = sender.send(Message::Close(close_frame)).await {
         warn!(client_hash = %client_hash, error = %e, "websocket_close_failed");
     }
     Ok(())
@@ -2,16 +2,64 @@ use anyhow::Result;
 use app::start_server;
 use dotenv::dotenv;
 use opentelemetry::trace::TracerProvider;
+use std::{path::PathBuf, process::ExitCode, sync::Arc};
 use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+use ultimatexo_core::ServerConfig;
 
 mod app;
 mod handlers;
 mod utils;
 
+struct CliArgs {
+    config_path: Option<PathBuf>,
+    print_config: bool,
+}
+
+fn parse_args() -> Result<CliArgs, String> {
+    let mut args = CliArgs {
+        config_path: std::env::var_os("ULTIMATEXO_CONFIG").map(PathBuf::from),
+        print_config: false,
+    };
+    let mut iter = std::env::args().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--print-config" => args.print_config = true,
+            "--config" => {
+                let path = iter.next().ok_or("--config requires a path")?;
+                args.config_path = Some(PathBuf::from(path));
+            }
+            other => match other.strip_prefix("--config=") {
+                Some(path) => args.config_path = Some(PathBuf::from(path)),
+                None => return Err(format!("unknown argument: {other}")),
+            },
+        }
+    }
+    Ok(args)
+}
+
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> Result<ExitCode> {
     dotenv().ok();
 
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{e}\nusage: server [--config <path>] [--print-config]");
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+    let config = match ServerConfig::load(args.config_path.as_deref()) {
+        Ok(config) => Arc::new(config),
+        Err(e) => {
+            eprintln!("{e}");
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+    if args.print_config {
+        print!("{}", config.to_redacted_toml());
+        return Ok(ExitCode::SUCCESS);
+    }
+
     let otel = utils::otel::init();
     if let Err(ref e) = otel {
         eprintln!("otel: {e}");
@@ -35,11 +83,11 @@ async fn main() -> Result<()> {
         .with(logs)
         .init();
 
-    let result = start_server().await;
+    let result = start_server(config).await;
 
     if let Ok(p) = otel {
         p.shutdown();
     }
 
-    result
+    result.map(|()| ExitCode::SUCCESS)
 }
@@ -1,3 +1,4 @@
+pub mod auth;
 pub mod messages;
 pub mod otel;
 pub mod real_ip;
@@ -0,0 +1,15 @@
+use axum::http::{HeaderMap, header};
+
+/// Whether the request carries `Authorization: Bearer <expected>`.
+pub fn bearer_token_matches(headers: &HeaderMap, expected: &str) -> bool {
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .unwrap_or_default();
+    constant_time_eq(provided.as_bytes(), expected.as_bytes())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
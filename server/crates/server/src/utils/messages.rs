@@ -1,11 +1,19 @@
 use crate::handlers::ConnectionContext;
 use std::{borrow::Cow, sync::Arc};
-use tokio::task::yield_now;
-use tracing::info;
+use tokio::{
+    task::yield_now,
+    time::{Instant, sleep},
+};
+use tracing::{debug, warn};
 use ultimatexo_core::{
-    Action, AppError, ClientMessage, Marker, Room, RoomType, ServerMessage, Status,
+    Action, AppError, ClientMessage, DomainEvent, Marker, Room, RoomLifecycleEvent, RoomType,
+    ServerMessage, Status, error::GameError,
 };
-use ultimatexo_services::GameAIService;
+use ultimatexo_services::{BotTurn, GameAIService, metrics};
+
+/// Moves a player must wait after the bot declines a draw before offering
+/// another one.
+const BOT_DRAW_OFFER_COOLDOWN: usize = 6;
 
 pub struct MessageHandler;
 
@@ -33,7 +41,6 @@ impl MessageHandler {
                 self.handle_draw_request(room, ctx, action).await
             }
             ClientMessage::Resign => self.handle_resign_request(room, ctx).await,
-            #[cfg(not(debug_assertions))]
             ClientMessage::Pong => self.handle_pong_response(ctx).await,
         }
     }
@@ -49,8 +56,8 @@ impl MessageHandler {
         let sanitized = sanitize_message_content(content)?;
 
         let message = ServerMessage::TextMessage {
-            content: sanitized,
-            player: player.info,
+            content: sanitized.clone(),
+            player: player.info.clone(),
         };
 
         room.tx
@@ -58,11 +65,12 @@ impl MessageHandler {
             .await
             .map_err(|e| AppError::internal_error(format!("Failed to broadcast message: {}", e)))?;
 
-        info!(
-            player_id = %ctx.player_id,
-            room_id = %room.info.id,
-            "chat_message_sent"
-        );
+        room.publish(DomainEvent::ChatMessage {
+            room_id: room.info.id.clone(),
+            player_id: ctx.player_id.clone(),
+            marker: player.info.marker,
+            content: sanitized,
+        });
 
         Ok(())
     }
@@ -84,29 +92,29 @@ impl MessageHandler {
         {
             return Err(AppError::not_player_turn());
         }
-        room.game.lock().await.make_move(mv)?;
-
-        let (board_state, game_status, next_board, next_player_marker) = {
-            let game = room.game.lock().await;
-            (
-                serde_json::to_string(&game.get_board()).unwrap_or_default(),
-                game.get_board_status(),
-                game.get_next_board(),
-                game.get_next_player().marker,
-            )
-        };
+        if let Err(e) = room.game.lock().await.make_move(mv) {
+            if let AppError::Game(GameError::InvalidMove {
+                reason,
+                expected_board,
+            }) = &e
+            {
+                warn!(
+                    player_id = %ctx.player_id,
+                    room_id = %room.info.id,
+                    move_board = mv[0],
+                    move_cell = mv[1],
+                    reason = ?reason,
+                    expected_board = ?expected_board,
+                    "move_rejected"
+                );
+            }
+            return Err(e);
+        }
+        room.finish_if_decided().await?;
 
-        info!(
-            player_id = %ctx.player_id,
-            room_id = %room.info.id,
-            move_board = mv[0],
-            move_cell = mv[1],
-            board_state = %board_state,
-            active_board = ?next_board,
-            next_player = ?next_player_marker,
-            game_status = ?game_status,
-            "player_moved"
-        );
+        room.publish_move(Some(ctx.player_id.clone()), current_player_marker, mv)
+            .await;
+        metrics().record_move("player");
 
         if room.info.room_type == RoomType::BotRoom
             && room
@@ -118,33 +126,29 @@ impl MessageHandler {
         {
             room.send_board().await;
             yield_now().await;
-            let mut game = room.game.lock().await;
-            if GameAIService::make_ai_move(&mut game, !current_player_marker)
-                .await
-                .is_err()
+            let bot_marker = !current_player_marker;
+            let turn = match GameAIService::play_turn(
+                &room.game,
+                bot_marker,
+                room.info.bot_level.as_ref(),
+                room.bot.and_then(|bot| bot.resign_below),
+            )
+            .await
             {
-                return Err(AppError::internal_error("Failed to make game move"));
-            }
-            drop(game);
-
-            let (bot_board_state, bot_game_status, bot_next_board, bot_next_player_marker) = {
-                let game = room.game.lock().await;
-                (
-                    serde_json::to_string(&game.get_board()).unwrap_or_default(),
-                    game.get_board_status(),
-                    game.get_next_board(),
-                    game.get_next_player().marker,
-                )
+                Ok(turn) => turn,
+                Err(_) => return Err(AppError::internal_error("Failed to make game move")),
             };
-            info!(
-                room_id = %room.info.id,
-                board_state = %bot_board_state,
-                active_board = ?bot_next_board,
-                next_player = ?bot_next_player_marker,
-                game_status = ?bot_game_status,
-                bot_level = ?room.info.bot_level,
-                "bot_moved"
-            );
+            match turn {
+                Some(BotTurn::Move(bot_move)) => {
+                    room.finish_if_decided().await?;
+                    room.publish_move(None, bot_marker, bot_move).await;
+                }
+                Some(BotTurn::Resign) => {
+                    let bot = room.get_opponent(&ctx.player_id).await?;
+                    resign(&room, &bot.id, bot_marker).await?;
+                }
+                None => {}
+            }
         }
 
         room.send_board().await;
@@ -170,26 +174,26 @@ impl MessageHandler {
                 if !game.has_pending_rematch() || game.is_pending_rematch_from(player_id) {
                     return Err(AppError::not_allowed());
                 }
-                game.rematch_game(None);
+                room.transition(RoomLifecycleEvent::RematchStarted).await?;
                 game.clear_rematch_request();
+                // Sent straight to the players so it arrives before any
+                // marker change, which refers to the new game.
+                room.send_to_players(ServerMessage::RematchRequest {
+                    action: action.clone(),
+                    player: marker,
+                })
+                .await;
+                room.prepare_rematch(&mut game).await;
+                game.rematch_game(None);
                 drop(game);
-                room.tx
-                    .send(ServerMessage::RematchRequest {
-                        action: action.clone(),
-                        player: marker,
-                    })
-                    .await
-                    .map_err(|e| {
-                        AppError::internal_error(format!("Failed to broadcast rematch: {}", e))
-                    })?;
                 room.send_board().await;
 
-                info!(
-                    player_id = %player_id,
-                    room_id = %room.info.id,
-                    action = ?action,
-                    "rematch_accepted"
-                );
+                room.publish(DomainEvent::Rematch {
+                    room_id: room.info.id.clone(),
+                    player_id: player_id.clone(),
+                    marker,
+                    action,
+                });
                 return Ok(());
             }
 
@@ -201,16 +205,40 @@ impl MessageHandler {
                     game.request_rematch(player_id.clone());
                 }
                 RoomType::LocalRoom => {
+                    room.transition(RoomLifecycleEvent::RematchStarted).await?;
                     game.rematch_game(None);
                     drop(game);
                     room.send_board().await;
                     return Ok(());
                 }
                 RoomType::BotRoom => {
-                    let difficulty = game.state.difficulty;
-                    game.rematch_game(Some(difficulty));
+                    if game.has_pending_rematch() {
+                        return Err(AppError::not_allowed());
+                    }
+                    // The bot answers like a player would, after a moment.
+                    game.request_rematch(player_id.clone());
                     drop(game);
-                    room.send_board().await;
+                    // Sent straight to the player, like the bot's answer.
+                    room.send_to_players(ServerMessage::RematchRequest {
+                        action: action.clone(),
+                        player: marker,
+                    })
+                    .await;
+                    room.publish(DomainEvent::Rematch {
+                        room_id: room.info.id.clone(),
+                        player_id: player_id.clone(),
+                        marker,
+                        action,
+                    });
+
+                    let delay = room.bot.map(|bot| bot.rematch_delay()).unwrap_or_default();
+                    let player_id = player_id.clone();
+                    tokio::spawn(async move {
+                        sleep(delay).await;
+                        if let Err(e) = accept_bot_rematch(&room, &player_id).await {
+                            debug!(room_id = %room.info.id, error = %e, "bot_rematch_skipped");
+                        }
+                    });
                     return Ok(());
                 }
             },
@@ -234,12 +262,12 @@ impl MessageHandler {
             .await
             .map_err(|e| AppError::internal_error(format!("Failed to broadcast rematch: {}", e)))?;
 
-        info!(
-            player_id = %player_id,
-            room_id = %room.info.id,
-            action = ?action,
-            "rematch_event"
-        );
+        room.publish(DomainEvent::Rematch {
+            room_id: room.info.id.clone(),
+            player_id: player_id.clone(),
+            marker,
+            action,
+        });
 
         Ok(())
     }
@@ -264,9 +292,11 @@ impl MessageHandler {
                     if !game.has_pending_draw() || game.is_pending_draw_from(player_id) {
                         return Err(AppError::not_allowed());
                     }
+                    room.transition(RoomLifecycleEvent::GameFinished).await?;
                     game.draw_game();
                     game.clear_draw_request();
                     drop(game);
+                    room.publish_game_finished().await;
                     room.tx
                         .send(ServerMessage::DrawRequest {
                             action: action.clone(),
@@ -278,12 +308,12 @@ impl MessageHandler {
                         })?;
                     room.send_board().await;
 
-                    info!(
-                        player_id = %player_id,
-                        room_id = %room.info.id,
-                        action = ?action,
-                        "draw_accepted"
-                    );
+                    room.publish(DomainEvent::DrawOffer {
+                        room_id: room.info.id.clone(),
+                        player_id: player_id.clone(),
+                        marker,
+                        action,
+                    });
                     return Ok(());
                 }
                 Action::Request => {
@@ -303,7 +333,70 @@ impl MessageHandler {
                     game.clear_draw_request();
                 }
             },
-            _ => {
+            RoomType::BotRoom => {
+                // The bot never offers a draw, so there is nothing to answer.
+                if !matches!(action, Action::Request) || game.has_pending_draw() {
+                    return Err(AppError::not_allowed());
+                }
+                let wait = game.draw_offer_wait(BOT_DRAW_OFFER_COOLDOWN);
+                if wait > 0 {
+                    return Err(AppError::draw_offer_too_soon(wait));
+                }
+                let bot = room.get_opponent(player_id).await?;
+                game.request_draw(player_id.clone());
+                let state = game.state.clone();
+                let offered_at = game.get_history().len();
+                drop(game);
+
+                let behaviour = room.bot.unwrap_or_default();
+                send_draw_message(&room, action.clone(), marker).await?;
+                room.publish(DomainEvent::DrawOffer {
+                    room_id: room.info.id.clone(),
+                    player_id: player_id.clone(),
+                    marker,
+                    action,
+                });
+
+                let score =
+                    GameAIService::evaluate(state, bot.info.marker, room.info.bot_level.clone())
+                        .await;
+                let mut game = room.game.lock().await;
+                // A new game started while the bot thought.
+                if !game.is_pending_draw_from(player_id) {
+                    return Ok(());
+                }
+                let score = score.inspect_err(|e| {
+                    warn!(room_id = %room.info.id, error = %e, "bot_draw_evaluation_failed");
+                });
+                // The offer also lapses if a move was played in the meantime.
+                let current = game.get_history().len() == offered_at
+                    && game.get_board_status().eq(&Status::InProgress);
+                let reply =
+                    if current && score.is_ok_and(|score| score < behaviour.accept_draw_below) {
+                        game.clear_draw_request();
+                        room.transition(RoomLifecycleEvent::GameFinished).await?;
+                        game.draw_game();
+                        drop(game);
+                        room.publish_game_finished().await;
+                        Action::Accept
+                    } else {
+                        game.decline_draw();
+                        drop(game);
+                        Action::Decline
+                    };
+                send_draw_message(&room, reply.clone(), bot.info.marker).await?;
+                if matches!(reply, Action::Accept) {
+                    room.send_board().await;
+                }
+                room.publish(DomainEvent::DrawOffer {
+                    room_id: room.info.id.clone(),
+                    player_id: bot.id,
+                    marker: bot.info.marker,
+                    action: reply,
+                });
+                return Ok(());
+            }
+            RoomType::LocalRoom => {
                 return Err(AppError::not_allowed());
             }
         };
@@ -316,12 +409,12 @@ impl MessageHandler {
             .await
             .map_err(|e| AppError::internal_error(format!("Failed to broadcast draw: {}", e)))?;
 
-        info!(
-            player_id = %player_id,
-            room_id = %room.info.id,
-            action = ?action,
-            "draw_event"
-        );
+        room.publish(DomainEvent::DrawOffer {
+            room_id: room.info.id.clone(),
+            player_id: player_id.clone(),
+            marker,
+            action,
+        });
 
         Ok(())
     }
@@ -336,37 +429,15 @@ impl MessageHandler {
             RoomType::LocalRoom => room.game.lock().await.get_current_player().marker,
             _ => room.get_player(player_id).await?.info.marker,
         };
-        if marker == Marker::X {
-            room.game
-                .lock()
-                .await
-                .set_board_status(Status::Won(Marker::O));
-            room.game.lock().await.increase_score(1);
-        } else {
-            room.game
-                .lock()
-                .await
-                .set_board_status(Status::Won(Marker::X));
-            room.game.lock().await.increase_score(0);
-        }
+        resign(&room, player_id, marker).await?;
         room.send_board().await;
-
-        info!(
-            player_id = %player_id,
-            room_id = %room.info.id,
-            marker = ?marker,
-            "player_resigned"
-        );
-
         Ok(())
     }
 
-    #[cfg(not(debug_assertions))]
     async fn handle_pong_response(&self, ctx: &ConnectionContext) -> Result<(), AppError> {
-        use tokio::time::Instant;
         let mut last_pong = ctx.last_pong.write().await;
         *last_pong = Instant::now();
-        tracing::debug!(player_id = %ctx.player_id, "pong_received");
+        debug!(player_id = %ctx.player_id, "pong_received");
 
         Ok(())
     }
@@ -438,3 +509,71 @@ pub fn sanitize_message_content(content: String) -> Result<String, AppError> {
 
     Ok(sanitized)
 }
+
+/// Ends the game in the opponent's favour.
+async fn resign(room: &Room, player_id: &str, marker: Marker) -> Result<(), AppError> {
+    {
+        let mut game = room.game.lock().await;
+        room.transition(RoomLifecycleEvent::GameFinished).await?;
+        game.set_board_status(Status::Won(!marker));
+        game.increase_score(if marker == Marker::X { 1 } else { 0 });
+    }
+    room.publish_game_finished().await;
+
+    room.publish(DomainEvent::PlayerResigned {
+        room_id: room.info.id.clone(),
+        player_id: player_id.to_string(),
+        marker,
+    });
+    Ok(())
+}
+
+async fn send_draw_message(room: &Room, action: Action, player: Marker) -> Result<(), AppError> {
+    room.tx
+        .send(ServerMessage::DrawRequest { action, player })
+        .await
+        .map_err(|e| AppError::internal_error(format!("Failed to broadcast draw: {}", e)))
+}
+
+/// Accepts the rematch `player_id` asked the bot for, unless it was
+/// withdrawn in the meantime.
+async fn accept_bot_rematch(room: &Room, player_id: &String) -> Result<(), AppError> {
+    let mut game = room.game.lock().await;
+    if !game.is_pending_rematch_from(player_id) {
+        return Ok(());
+    }
+    game.clear_rematch_request();
+    room.transition(RoomLifecycleEvent::RematchStarted).await?;
+    let bot = room.get_opponent(player_id).await?;
+    room.send_to_players(ServerMessage::RematchRequest {
+        action: Action::Accept,
+        player: bot.info.marker,
+    })
+    .await;
+    room.publish(DomainEvent::Rematch {
+        room_id: room.info.id.clone(),
+        player_id: bot.id.clone(),
+        marker: bot.info.marker,
+        action: Action::Accept,
+    });
+
+    if room.info.adaptive_difficulty {
+        let max_skill = room.bot.unwrap_or_default().max_adaptive_difficulty;
+        GameAIService::adapt(&mut game, bot.info.marker, max_skill);
+    }
+    let difficulty = game.state.difficulty;
+    room.prepare_rematch(&mut game).await;
+    game.rematch_game(Some(difficulty));
+    let bot_marker = room.get_player(&bot.id).await?.info.marker;
+    let bot_to_move = game.get_current_player().marker == bot_marker;
+    drop(game);
+    if bot_to_move
+        && let Some(bot_move) =
+            GameAIService::make_ai_move(&room.game, bot_marker, room.info.bot_level.as_ref())
+                .await?
+    {
+        room.publish_move(None, bot_marker, bot_move).await;
+    }
+    room.send_board().await;
+    Ok(())
+}
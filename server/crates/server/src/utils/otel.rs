@@ -1,14 +1,23 @@
-use anyhow::anyhow;
+use anyhow::{Context, anyhow, bail};
 use chrono::Local;
-use opentelemetry::KeyValue;
-use opentelemetry_otlp::{LogExporter, SpanExporter, WithExportConfig, WithHttpConfig};
-use opentelemetry_sdk::{Resource, logs::SdkLoggerProvider, trace::SdkTracerProvider};
+use opentelemetry::{KeyValue, global};
+use opentelemetry_otlp::{
+    LogExporter, MetricExporter, Protocol, SpanExporter, WithExportConfig, WithHttpConfig,
+};
+use opentelemetry_sdk::{
+    Resource,
+    logs::SdkLoggerProvider,
+    metrics::SdkMeterProvider,
+    trace::{Sampler, SdkTracerProvider},
+};
 use sha2::Digest;
 use std::{collections::HashMap, env};
+use ultimatexo_services::metrics;
 
 pub struct Providers {
     pub tracer: SdkTracerProvider,
     pub logger: SdkLoggerProvider,
+    pub meter: Option<SdkMeterProvider>,
 }
 
 impl Providers {
@@ -17,18 +26,156 @@ impl Providers {
         let _ = self.logger.force_flush();
         let _ = self.tracer.shutdown();
         let _ = self.logger.shutdown();
+        if let Some(meter) = &self.meter {
+            let _ = meter.force_flush();
+            let _ = meter.shutdown();
+        }
     }
 }
 
-pub fn init() -> anyhow::Result<Providers> {
-    if env::var("AXIOM_ENABLED").unwrap_or_default() != "true" {
-        return Err(anyhow!("AXIOM_ENABLED not set"));
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OtlpProtocol {
+    HttpProtobuf,
+    HttpJson,
+}
+
+impl OtlpProtocol {
+    fn parse(value: &str) -> anyhow::Result<Self> {
+        match value.trim() {
+            "http/protobuf" => Ok(Self::HttpProtobuf),
+            "http/json" => Ok(Self::HttpJson),
+            other => {
+                bail!("unsupported OTLP protocol {other:?}, expected http/protobuf or http/json")
+            }
+        }
+    }
+
+    fn to_sdk(self) -> Protocol {
+        match self {
+            Self::HttpProtobuf => Protocol::HttpBinary,
+            Self::HttpJson => Protocol::HttpJson,
+        }
     }
+}
+
+/// Where and how telemetry is exported. Read from the standard `OTEL_*`
+/// variables, or from `AXIOM_*` when the Axiom preset is selected.
+#[derive(Debug, Clone)]
+pub struct OtlpConfig {
+    /// Base URL; `/v1/traces`, `/v1/logs` and `/v1/metrics` are appended.
+    pub endpoint: String,
+    pub protocol: OtlpProtocol,
+    pub headers: HashMap<String, String>,
+    /// Merged over `headers` for the logs exporter only.
+    pub logs_headers: HashMap<String, String>,
+    pub sampling_ratio: f64,
+    pub metrics_enabled: bool,
+}
 
-    let token = env::var("AXIOM_TOKEN").map_err(|_| anyhow!("AXIOM_TOKEN missing"))?;
-    let dataset = env::var("AXIOM_DATASET").unwrap_or_else(|_| "ultimatexo".into());
-    let logs_dataset = env::var("AXIOM_DATASET_LOGS").unwrap_or_else(|_| dataset.clone());
-    let domain = env::var("AXIOM_DOMAIN").unwrap_or_else(|_| "api.axiom.co".into());
+impl OtlpConfig {
+    pub fn from_env() -> anyhow::Result<Self> {
+        let preset = env_var("OTEL_PRESET").unwrap_or_default();
+        let mut config = if preset.eq_ignore_ascii_case("axiom")
+            || env::var("AXIOM_ENABLED").unwrap_or_default() == "true"
+        {
+            Self::axiom()?
+        } else if !preset.is_empty() {
+            bail!("unknown OTEL_PRESET {preset:?}");
+        } else {
+            let endpoint = env_var("OTEL_EXPORTER_OTLP_ENDPOINT")
+                .ok_or_else(|| anyhow!("OTEL_EXPORTER_OTLP_ENDPOINT not set"))?;
+            Self {
+                endpoint,
+                protocol: OtlpProtocol::HttpProtobuf,
+                headers: HashMap::new(),
+                logs_headers: HashMap::new(),
+                sampling_ratio: 1.0,
+                metrics_enabled: false,
+            }
+        };
+
+        if let Some(protocol) = env_var("OTEL_EXPORTER_OTLP_PROTOCOL") {
+            config.protocol = OtlpProtocol::parse(&protocol)?;
+        }
+        if let Some(headers) = env_var("OTEL_EXPORTER_OTLP_HEADERS") {
+            config.headers.extend(parse_headers(&headers)?);
+        }
+        if let Some(ratio) = env_var("OTEL_TRACES_SAMPLER_ARG") {
+            config.sampling_ratio = ratio
+                .parse()
+                .context("OTEL_TRACES_SAMPLER_ARG must be a number")?;
+        }
+        if let Some(enabled) = env_var("OTEL_METRICS_ENABLED") {
+            config.metrics_enabled = enabled == "true";
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn axiom() -> anyhow::Result<Self> {
+        let token = env::var("AXIOM_TOKEN").map_err(|_| anyhow!("AXIOM_TOKEN missing"))?;
+        let dataset = env::var("AXIOM_DATASET").unwrap_or_else(|_| "ultimatexo".into());
+        let logs_dataset = env::var("AXIOM_DATASET_LOGS").unwrap_or_else(|_| dataset.clone());
+        let domain = env::var("AXIOM_DOMAIN").unwrap_or_else(|_| "api.axiom.co".into());
+
+        Ok(Self {
+            endpoint: format!("https://{domain}"),
+            protocol: OtlpProtocol::HttpJson,
+            headers: HashMap::from([
+                ("Authorization".into(), format!("Bearer {token}")),
+                ("X-Axiom-Dataset".into(), dataset),
+            ]),
+            logs_headers: HashMap::from([("X-Axiom-Dataset".into(), logs_dataset)]),
+            sampling_ratio: 1.0,
+            metrics_enabled: false,
+        })
+    }
+
+    fn validate(&self) -> anyhow::Result<()> {
+        if !(self.endpoint.starts_with("http://") || self.endpoint.starts_with("https://")) {
+            bail!(
+                "OTLP endpoint must be an http(s) URL, got {:?}",
+                self.endpoint
+            );
+        }
+        if !(0.0..=1.0).contains(&self.sampling_ratio) {
+            bail!("sampling ratio must be between 0 and 1");
+        }
+        Ok(())
+    }
+
+    fn signal_url(&self, signal: &str) -> String {
+        format!("{}/v1/{signal}", self.endpoint.trim_end_matches('/'))
+    }
+
+    fn signal_headers(&self, overrides: &HashMap<String, String>) -> HashMap<String, String> {
+        let mut headers = self.headers.clone();
+        headers.extend(overrides.clone());
+        headers
+    }
+}
+
+/// Treats empty variables, as left behind by compose defaults, as unset.
+fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.trim().is_empty())
+}
+
+/// Parses the `key=value,key2=value2` format of `OTEL_EXPORTER_OTLP_HEADERS`.
+fn parse_headers(raw: &str) -> anyhow::Result<HashMap<String, String>> {
+    raw.split(',')
+        .filter(|pair| !pair.trim().is_empty())
+        .map(|pair| {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or_else(|| anyhow!("invalid OTLP header {pair:?}, expected key=value"))?;
+            Ok((key.trim().to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+pub fn init() -> anyhow::Result<Providers> {
+    let config = OtlpConfig::from_env()?;
 
     let resource = Resource::builder()
         .with_attributes([
@@ -41,45 +188,88 @@ pub fn init() -> anyhow::Result<Providers> {
         ])
         .build();
 
-    let auth = format!("Bearer {token}");
-
     let trace = SpanExporter::builder()
         .with_http()
-        .with_endpoint(format!("https://{domain}/v1/traces"))
-        .with_protocol(opentelemetry_otlp::Protocol::HttpJson)
-        .with_headers(HashMap::from([
-            ("Authorization".into(), auth.clone()),
-            ("X-Axiom-Dataset".into(), dataset),
-        ]))
+        .with_endpoint(config.signal_url("traces"))
+        .with_protocol(config.protocol.to_sdk())
+        .with_headers(config.headers.clone())
         .build()
         .map_err(|e| anyhow!("trace exporter failed: {e}"))?;
 
     let logs = LogExporter::builder()
         .with_http()
-        .with_endpoint(format!("https://{domain}/v1/logs"))
-        .with_protocol(opentelemetry_otlp::Protocol::HttpJson)
-        .with_headers(HashMap::from([
-            ("Authorization".into(), auth),
-            ("X-Axiom-Dataset".into(), logs_dataset),
-        ]))
+        .with_endpoint(config.signal_url("logs"))
+        .with_protocol(config.protocol.to_sdk())
+        .with_headers(config.signal_headers(&config.logs_headers))
         .build()
         .map_err(|e| anyhow!("log exporter failed: {e}"))?;
 
+    let meter = if config.metrics_enabled {
+        let exporter = MetricExporter::builder()
+            .with_http()
+            .with_endpoint(config.signal_url("metrics"))
+            .with_protocol(config.protocol.to_sdk())
+            .with_headers(config.headers.clone())
+            .build()
+            .map_err(|e| anyhow!("metric exporter failed: {e}"))?;
+        let provider = SdkMeterProvider::builder()
+            .with_resource(resource.clone())
+            .with_periodic_exporter(exporter)
+            .build();
+        global::set_meter_provider(provider.clone());
+        register_metric_bridge();
+        Some(provider)
+    } else {
+        None
+    };
+
     Ok(Providers {
         tracer: SdkTracerProvider::builder()
             .with_resource(resource.clone())
+            .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(
+                config.sampling_ratio,
+            ))))
             .with_batch_exporter(trace)
             .build(),
         logger: SdkLoggerProvider::builder()
             .with_resource(resource)
             .with_batch_exporter(logs)
             .build(),
+        meter,
     })
 }
 
-pub fn hash_ip(ip: &str) -> String {
-    let salt =
-        env::var("IP_HASH_SALT").unwrap_or_else(|_| Local::now().format("%Y%m%d").to_string());
+/// Mirrors the Prometheus counters into OTLP so both pipelines report the
+/// same numbers.
+fn register_metric_bridge() {
+    let meter = global::meter("ultimatexo-server");
+    meter
+        .u64_observable_counter("ultimatexo.moves")
+        .with_callback(|observer| {
+            for actor in ["player", "bot"] {
+                let count = metrics().moves.with_label_values(&[actor]).get();
+                observer.observe(count, &[KeyValue::new("actor", actor)]);
+            }
+        })
+        .build();
+    meter
+        .u64_observable_counter("ultimatexo.cleanup_timeouts")
+        .with_callback(|observer| observer.observe(metrics().cleanup_timeouts.get(), &[]))
+        .build();
+    meter
+        .u64_observable_counter("ultimatexo.ws_send_failures")
+        .with_callback(|observer| observer.observe(metrics().ws_send_failures.get(), &[]))
+        .build();
+    meter
+        .i64_observable_gauge("ultimatexo.rate_limiter_keys")
+        .with_callback(|observer| observer.observe(metrics().rate_limiter_keys.get(), &[]))
+        .build();
+}
+
+pub fn hash_ip(ip: &str, salt: Option<&str>) -> String {
+    let salt = salt
+        .map(str::to_string)
+        .unwrap_or_else(|| Local::now().format("%Y%m%d").to_string());
     let mut h = sha2::Sha256::new();
     h.update(ip.as_bytes());
     h.update(salt.as_bytes());
@@ -1,20 +1,24 @@
+use crate::{EventBus, LobbyFeed, metrics};
 use axum::extract::ws::Message;
 use dashmap::DashMap;
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 use tokio_util::sync::CancellationToken;
-use tracing::{debug, info, warn};
+use tracing::{debug, warn};
 use ultimatexo_core::{
     RoomType,
-    domain::RoomRules,
+    domain::{DomainEvent, RoomLifecycleEvent, RoomPhase, RoomRules},
     models::{PlayerAction, Room, SerizlizedPlayer, ServerMessage, Status},
 };
 
 #[derive(Default)]
-pub struct CleanupService;
+pub struct CleanupService {
+    lobby: LobbyFeed,
+    events: EventBus,
+}
 
 impl CleanupService {
-    pub fn new() -> Self {
-        Self
+    pub fn new(lobby: LobbyFeed, events: EventBus) -> Self {
+        Self { lobby, events }
     }
 
     pub async fn schedule_room_cleanup(
@@ -25,7 +29,12 @@ impl CleanupService {
         cleanup_token: CancellationToken,
         game_rules: Arc<dyn RoomRules>,
     ) {
-        let timeout_duration = game_rules.get_cleanup_timeout();
+        // A removed player cannot come back, so there is nothing to wait for.
+        let timeout_duration = if room.is_removed(&disconnected_player_id).await {
+            Duration::ZERO
+        } else {
+            game_rules.get_cleanup_timeout()
+        };
         let timeout_game_state = game_rules.get_timeout_game_state(
             room.get_player(&disconnected_player_id)
                 .await
@@ -34,6 +43,8 @@ impl CleanupService {
                 .marker,
         );
         let room_id = room.info.id.clone();
+        let lobby = self.lobby.clone();
+        let events = self.events.clone();
         debug!(
             room_id = %room_id,
             timeout = ?timeout_duration,
@@ -48,7 +59,9 @@ impl CleanupService {
                         disconnected_player_id,
                         room,
                         rooms,
-                        timeout_game_state
+                        timeout_game_state,
+                        lobby,
+                        events,
                     ).await;
                 }
                 _ = cleanup_token.cancelled() => {
@@ -64,14 +77,26 @@ impl CleanupService {
         room: Arc<Room>,
         rooms: Arc<DashMap<String, Arc<Room>>>,
         timeout_game_state: Status,
+        lobby: LobbyFeed,
+        events: EventBus,
     ) {
         if !rooms.contains_key(&room_id) {
             debug!(room_id = %room_id, "room_already_removed");
             return;
         }
-        {
-            let mut game_lock = room.game.lock().await;
-            game_lock.set_board_status(timeout_game_state);
+        if room.phase().await == RoomPhase::Paused {
+            let mut game = room.game.lock().await;
+            match room.transition(RoomLifecycleEvent::GameFinished).await {
+                Ok(_) => {
+                    game.set_board_status(timeout_game_state);
+                    drop(game);
+                    room.publish_game_finished().await;
+                }
+                Err(e) => debug!(room_id = %room_id, error = %e, "timeout_finish_skipped"),
+            }
+        }
+        if let Err(e) = room.transition(RoomLifecycleEvent::ClosingStarted).await {
+            debug!(room_id = %room_id, error = %e, "room_already_closing");
         }
         if room.info.room_type == RoomType::Standard {
             let timed_out_player = room.get_player(&disconnected_player_id).await.unwrap();
@@ -90,6 +115,7 @@ impl CleanupService {
                     error = ?e,
                     "send_board_failed"
                 );
+                metrics().ws_send_failures.inc();
             }
             if let Err(e) = opponent_tx.send(timeout_msg) {
                 warn!(
@@ -98,6 +124,7 @@ impl CleanupService {
                     error = ?e,
                     "send_timeout_failed"
                 );
+                metrics().ws_send_failures.inc();
             }
             if let Err(e) = opponent_tx.send(ServerMessage::WebsocketMessage(Message::Close(None)))
             {
@@ -107,18 +134,22 @@ impl CleanupService {
                     error = ?e,
                     "send_close_failed"
                 );
+                metrics().ws_send_failures.inc();
             }
         }
 
-        info!(
-            room_id = %room_id,
-            player_id = %disconnected_player_id,
-            timeout_game_state = ?timeout_game_state,
-            "game_timeout"
-        );
+        metrics().cleanup_timeouts.inc();
+        events.publish(DomainEvent::PlayerTimedOut {
+            room_id: room_id.clone(),
+            player_id: disconnected_player_id,
+            result: timeout_game_state,
+        });
 
         if rooms.remove(&room_id).is_none() {
             debug!(room_id = %room_id, "room_missing_timeout_cleanup");
+        } else {
+            lobby.room_removed(&room.info);
+            events.publish(DomainEvent::RoomRemoved { room_id });
         }
     }
 
@@ -131,11 +162,17 @@ impl CleanupService {
         if let Some(token) = room.deletion_token.lock().await.take() {
             token.cancel();
         }
-
-        info!(room_id = %room_id, "room_removed");
+        if let Err(e) = room.transition(RoomLifecycleEvent::ClosingStarted).await {
+            debug!(room_id = %room_id, error = %e, "room_already_closing");
+        }
 
         if rooms.remove(room_id).is_none() {
             debug!(room_id = %room_id, "room_missing");
+        } else {
+            self.lobby.room_removed(&room.info);
+            self.events.publish(DomainEvent::RoomRemoved {
+                room_id: room_id.to_string(),
+            });
         }
     }
 }
@@ -0,0 +1,210 @@
+use crate::metrics;
+use std::{
+    future::Future,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+use tokio::{
+    sync::{Notify, broadcast, watch},
+    task::JoinHandle,
+    time::timeout,
+};
+use tracing::warn;
+use ultimatexo_core::domain::DomainEvent;
+
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// In-process fan-out of domain events. Rooms publish through the sender
+/// handed to them at creation and each event is logged as it is published;
+/// other side effects (persistence, webhooks, ...) attach as
+/// independent subscribers.
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<DomainEvent>,
+    flush: Arc<FlushState>,
+}
+
+/// Lets `flush` wait for every subscriber to catch up and for the work
+/// they spawned through `spawn_tracked` to finish.
+struct FlushState {
+    requested: watch::Sender<u64>,
+    acks: std::sync::Mutex<Vec<Arc<AtomicU64>>>,
+    in_flight: AtomicUsize,
+    progress: Notify,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            tx,
+            flush: Arc::new(FlushState {
+                requested: watch::Sender::new(0),
+                acks: std::sync::Mutex::new(Vec::new()),
+                in_flight: AtomicUsize::new(0),
+                progress: Notify::new(),
+            }),
+        }
+    }
+
+    pub fn sender(&self) -> broadcast::Sender<DomainEvent> {
+        self.tx.clone()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<DomainEvent> {
+        self.tx.subscribe()
+    }
+
+    pub fn publish(&self, event: DomainEvent) {
+        event.log();
+        // Nobody listening is not an error; the bus is best-effort.
+        let _ = self.tx.send(event);
+    }
+
+    /// Runs `handler` for every event until the bus is dropped. A subscriber
+    /// that falls behind skips the missed events instead of stalling the
+    /// publishers.
+    pub fn spawn_subscriber<F, Fut>(&self, name: &'static str, mut handler: F) -> JoinHandle<()>
+    where
+        F: FnMut(DomainEvent) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send,
+    {
+        let mut rx = self.subscribe();
+        let mut requested = self.flush.requested.subscribe();
+        let acked = Arc::new(AtomicU64::new(0));
+        self.flush
+            .acks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(acked.clone());
+        let flush = self.flush.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    biased;
+                    event = rx.recv() => match event {
+                        Ok(event) => handler(event).await,
+                        Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                            warn!(subscriber = name, skipped, "event_subscriber_lagged");
+                            metrics()
+                                .events_dropped
+                                .with_label_values(&[name])
+                                .inc_by(skipped);
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                    Ok(()) = requested.changed() => {}
+                }
+                // Caught up: everything published before the latest flush
+                // request has been handled.
+                if rx.is_empty() {
+                    acked.store(*requested.borrow_and_update(), Ordering::SeqCst);
+                    flush.progress.notify_waiters();
+                }
+            }
+        })
+    }
+
+    /// Spawns work a subscriber started for an event, such as a delivery
+    /// with retries, so that `flush` waits for it.
+    pub fn spawn_tracked<Fut>(&self, task: Fut) -> JoinHandle<()>
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        struct Done(Arc<FlushState>);
+        impl Drop for Done {
+            fn drop(&mut self) {
+                self.0.in_flight.fetch_sub(1, Ordering::SeqCst);
+                self.0.progress.notify_waiters();
+            }
+        }
+
+        self.flush.in_flight.fetch_add(1, Ordering::SeqCst);
+        let done = Done(self.flush.clone());
+        tokio::spawn(async move {
+            let _done = done;
+            task.await;
+        })
+    }
+
+    /// Waits until every subscriber has handled the events published so far
+    /// and their tracked work has finished. Returns false on timeout.
+    pub async fn flush(&self, limit: Duration) -> bool {
+        self.flush
+            .requested
+            .send_modify(|generation| *generation += 1);
+        let target = *self.flush.requested.borrow();
+        timeout(limit, async {
+            loop {
+                let progress = self.flush.progress.notified();
+                if self.is_flushed(target) {
+                    return;
+                }
+                progress.await;
+            }
+        })
+        .await
+        .is_ok()
+    }
+
+    fn is_flushed(&self, target: u64) -> bool {
+        let acks = self.flush.acks.lock().unwrap_or_else(|e| e.into_inner());
+        acks.iter()
+            .all(|acked| acked.load(Ordering::SeqCst) >= target)
+            && self.flush.in_flight.load(Ordering::SeqCst) == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    fn room_removed() -> DomainEvent {
+        DomainEvent::RoomRemoved {
+            room_id: "123456".to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn flush_waits_for_subscribers_and_tracked_work() {
+        let bus = EventBus::new();
+        let handled = Arc::new(AtomicUsize::new(0));
+        let tracker = bus.clone();
+        let counter = handled.clone();
+        bus.spawn_subscriber("test", move |_| {
+            let counter = counter.clone();
+            tracker.spawn_tracked(async move {
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+            async {}
+        });
+        for _ in 0..3 {
+            bus.publish(room_removed());
+        }
+
+        assert!(bus.flush(Duration::from_secs(5)).await);
+        assert_eq!(handled.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn flush_gives_up_after_the_limit() {
+        let bus = EventBus::new();
+        bus.spawn_subscriber("slow", |_| tokio::time::sleep(Duration::from_secs(5)));
+        bus.publish(room_removed());
+
+        let started = Instant::now();
+        assert!(!bus.flush(Duration::from_millis(100)).await);
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
@@ -1,30 +1,210 @@
-use ultimatexo_ai::MinimaxAI;
-use ultimatexo_core::{AppError, GameEngine, Marker};
+use crate::metrics;
+use std::time::Instant;
+use tokio::{sync::Mutex, task::spawn_blocking};
+use ultimatexo_ai::{AIConfig, MinimaxAI};
+use ultimatexo_core::{
+    AppError, BotConfig, BotEngine, BotLevel, BotStrength, GameEngine, GameState, Marker, Status,
+};
 
 pub struct GameAIService;
 
+/// Skill change per point of running score away from an even 0.5, before
+/// damping. The first decided game moves the bot by up to two levels.
+const ADAPT_GAIN: f64 = 4.0;
+
+/// What the bot does when it is its turn.
+pub enum BotTurn {
+    Move([usize; 2]),
+    Resign,
+}
+
 impl GameAIService {
-    pub async fn make_ai_move(game: &mut GameEngine, ai_marker: Marker) -> Result<(), AppError> {
-        let mut ai = MinimaxAI::with_level(game.state.difficulty).unwrap();
-        if let Some(ai_move) = ai.find_best_move(&game.state, ai_marker)
-            && game
-                .make_move([ai_move.board_index, ai_move.cell_index])
-                .is_ok()
-        {
-            return Ok(());
+    /// Plays the bot's move in `game`. Returns `None` when the game moved on
+    /// while the bot was searching.
+    pub async fn make_ai_move(
+        game: &Mutex<GameEngine>,
+        ai_marker: Marker,
+        level: Option<&BotLevel>,
+    ) -> Result<Option<[usize; 2]>, AppError> {
+        match Self::play_turn(game, ai_marker, level, None).await? {
+            Some(BotTurn::Move(mv)) => Ok(Some(mv)),
+            Some(BotTurn::Resign) => Err(AppError::ai_move_failed()),
+            None => Ok(None),
         }
-        Err(AppError::ai_move_failed())
     }
 
-    pub async fn make_random_ai_move(game: &mut GameEngine) -> Result<(), AppError> {
-        let ai = MinimaxAI::with_level(game.state.difficulty).unwrap();
-        if let Some(ai_move) = ai.find_random_move(&game.state)
-            && game
-                .make_move([ai_move.board_index, ai_move.cell_index])
-                .is_ok()
+    /// Searches for a move and plays it, or resigns instead when the search
+    /// scores the position below `resign_below`. The search runs on a
+    /// blocking thread against a copy of the game, so neither a runtime
+    /// worker nor the game lock is held while it thinks. Returns `None`
+    /// when the game moved on in the meantime, say because the player
+    /// resigned.
+    pub async fn play_turn(
+        game: &Mutex<GameEngine>,
+        ai_marker: Marker,
+        level: Option<&BotLevel>,
+        resign_below: Option<i32>,
+    ) -> Result<Option<BotTurn>, AppError> {
+        let (state, played) = {
+            let game = game.lock().await;
+            (game.state.clone(), game.get_history().len())
+        };
+        let mut ai = Self::engine(&state)?;
+        let level = level.cloned();
+        let (best_move, score) = spawn_blocking(move || {
+            let started = Instant::now();
+            let best_move = match state.bot_strength.map(|strength| strength.engine) {
+                Some(BotEngine::Random) => ai.find_random_move(&state),
+                _ => ai.find_best_move(&state, ai_marker),
+            };
+            metrics().observe_bot_search(
+                level.as_ref(),
+                started.elapsed().as_secs_f64(),
+                ai.nodes_searched(),
+            );
+            (best_move, ai.last_evaluation())
+        })
+        .await
+        .map_err(|e| AppError::internal_error(format!("Bot search failed: {}", e)))?;
+
+        let mut game = game.lock().await;
+        if game.get_history().len() != played
+            || game.get_board_status() != Status::InProgress
+            || game.get_current_player().marker != ai_marker
+        {
+            return Ok(None);
+        }
+        if let (Some(threshold), Some(score)) = (resign_below, score)
+            && score < threshold
         {
-            return Ok(());
+            return Ok(Some(BotTurn::Resign));
+        }
+        if let Some(ai_move) = best_move {
+            let mv = [ai_move.board_index, ai_move.cell_index];
+            if game.make_move(mv).is_ok() {
+                metrics().record_move("bot");
+                return Ok(Some(BotTurn::Move(mv)));
+            }
         }
         Err(AppError::ai_move_failed())
     }
+
+    /// The bot's score for `state`, searched at its level on a blocking
+    /// thread so the search does not stall other connections.
+    pub async fn evaluate(
+        state: GameState,
+        ai_marker: Marker,
+        level: Option<BotLevel>,
+    ) -> Result<i32, AppError> {
+        let mut ai = Self::engine(&state)?;
+        spawn_blocking(move || {
+            let started = Instant::now();
+            let score = ai.evaluate_position(&state, ai_marker);
+            metrics().observe_bot_search(
+                level.as_ref(),
+                started.elapsed().as_secs_f64(),
+                ai.nodes_searched(),
+            );
+            score
+        })
+        .await
+        .map_err(|e| AppError::internal_error(format!("Bot evaluation failed: {}", e)))
+    }
+
+    pub fn strength(skill: f64) -> BotStrength {
+        let config = AIConfig::from_skill(skill);
+        BotStrength {
+            skill: skill.clamp(1.0, 10.0),
+            max_depth: config.max_depth,
+            max_time_ms: config.max_time.unwrap_or_default().as_millis() as u64,
+            random_move_chance: config.random_move_chance,
+            mistake_chance: config.mistake_chance,
+            engine: BotEngine::Minimax,
+        }
+    }
+
+    /// The starting strength for a room created with `config`.
+    pub fn configured(config: &BotConfig) -> BotStrength {
+        match config {
+            BotConfig::Level(level) => Self::strength(f64::from(*level)),
+            BotConfig::Custom(custom) => BotStrength {
+                skill: f64::from(config.difficulty()),
+                max_depth: custom.max_depth,
+                max_time_ms: custom.max_time_ms,
+                random_move_chance: 0.0,
+                mistake_chance: custom.mistake_chance,
+                engine: custom.engine,
+            },
+        }
+    }
+
+    /// Moves the bot's skill towards an even score after a decided game, in
+    /// proportion to how far the player's running score is from 0.5. Steps
+    /// shrink as games are played, so the skill settles instead of swinging
+    /// between two levels.
+    pub fn adapt(game: &mut GameEngine, bot_marker: Marker, max_skill: u8) {
+        let Some(strength) = game.state.bot_strength else {
+            return;
+        };
+        let player_score = match game.get_board_status() {
+            Status::Won(winner) if winner == bot_marker => 0.0,
+            Status::Won(_) => 1.0,
+            Status::Draw => 0.5,
+            _ => return,
+        };
+        let running_score = game.state.adaptive.add(player_score);
+        let damping = (game.state.adaptive.games as f64).sqrt();
+        let step = ADAPT_GAIN * (running_score - 0.5) / damping;
+        let skill = (strength.skill + step).clamp(1.0, f64::from(max_skill));
+        game.state.bot_strength = Some(Self::strength(skill));
+    }
+
+    fn engine(state: &GameState) -> Result<MinimaxAI, AppError> {
+        match state.bot_strength {
+            Some(strength) => Ok(MinimaxAI::new(AIConfig::from_strength(&strength))),
+            None => MinimaxAI::with_level(state.difficulty),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ultimatexo_core::{MatchFormat, RuleSet};
+
+    /// Plays `games` against a player who beats any bot below `level` and
+    /// loses to the rest, returning the skill after each game.
+    fn adapt_against(level: f64, games: usize) -> Vec<f64> {
+        let mut game = GameEngine::new(None, RuleSet::default(), MatchFormat::Open, None);
+        game.state.bot_strength = Some(GameAIService::strength(3.0));
+        (0..games)
+            .map(|_| {
+                let skill = game.state.bot_strength.unwrap().skill;
+                let winner = if skill < level { Marker::X } else { Marker::O };
+                game.set_board_status(Status::Won(winner));
+                GameAIService::adapt(&mut game, Marker::O, 10);
+                game.state.bot_strength.unwrap().skill
+            })
+            .collect()
+    }
+
+    #[test]
+    fn adapt_settles_near_the_players_level() {
+        let skills = adapt_against(6.5, 60);
+        let steps: Vec<f64> = skills.windows(2).map(|w| (w[1] - w[0]).abs()).collect();
+        assert!(steps[50..].iter().all(|&step| step < 0.25), "{skills:?}");
+        assert!(
+            skills[50..].iter().all(|skill| (5.5..7.5).contains(skill)),
+            "{skills:?}"
+        );
+    }
+
+    #[test]
+    fn adapt_ignores_games_in_progress() {
+        let mut game = GameEngine::new(None, RuleSet::default(), MatchFormat::Open, None);
+        game.state.bot_strength = Some(GameAIService::strength(5.0));
+        GameAIService::adapt(&mut game, Marker::O, 10);
+        assert_eq!(game.state.bot_strength.unwrap().skill, 5.0);
+        assert_eq!(game.state.adaptive.games, 0);
+    }
 }
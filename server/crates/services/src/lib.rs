@@ -1,7 +1,15 @@
 mod cleanup_service;
+mod event_bus;
 mod game_ai_service;
+mod lobby_feed;
+mod metrics;
 mod room_service;
+mod webhooks;
 
 pub use cleanup_service::CleanupService;
-pub use game_ai_service::GameAIService;
+pub use event_bus::EventBus;
+pub use game_ai_service::{BotTurn, GameAIService};
+pub use lobby_feed::LobbyFeed;
+pub use metrics::{Metrics, metrics};
 pub use room_service::RoomService;
+pub use webhooks::{WebhookConfig, WebhookEvent, spawn_webhooks};
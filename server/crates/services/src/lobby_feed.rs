@@ -0,0 +1,79 @@
+use tokio::sync::broadcast;
+use tracing::debug;
+use ultimatexo_core::models::{LobbyEvent, RoomInfo};
+
+const LOBBY_CHANNEL_CAPACITY: usize = 256;
+
+#[derive(Clone)]
+pub struct LobbyFeed {
+    tx: broadcast::Sender<LobbyEvent>,
+}
+
+impl Default for LobbyFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LobbyFeed {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(LOBBY_CHANNEL_CAPACITY);
+        Self { tx }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LobbyEvent> {
+        self.tx.subscribe()
+    }
+
+    pub fn room_created(&self, info: &RoomInfo) {
+        if info.is_public {
+            self.publish(LobbyEvent::RoomCreated(info.clone()));
+        }
+    }
+
+    pub fn room_updated(&self, info: &RoomInfo) {
+        if info.is_public {
+            self.publish(LobbyEvent::RoomUpdated(info.clone()));
+        }
+    }
+
+    pub fn room_filled(&self, info: &RoomInfo) {
+        if info.is_public {
+            self.publish(LobbyEvent::RoomFilled(info.clone()));
+        }
+    }
+
+    /// Sends `info` as filled once every seat is taken, otherwise as updated.
+    pub fn room_changed(&self, info: &RoomInfo) {
+        if info.is_full() {
+            self.room_filled(info);
+        } else {
+            self.room_updated(info);
+        }
+    }
+
+    pub fn room_removed(&self, info: &RoomInfo) {
+        if info.is_public {
+            self.publish(LobbyEvent::RoomRemoved {
+                id: info.id.clone(),
+            });
+        }
+    }
+
+    pub fn announce(&self, message: &str) {
+        self.publish(LobbyEvent::Announcement {
+            message: message.to_string(),
+        });
+    }
+
+    pub fn shutdown_warning(&self, remaining_secs: u64) {
+        self.publish(LobbyEvent::ShutdownWarning { remaining_secs });
+    }
+
+    fn publish(&self, event: LobbyEvent) {
+        // Sending only fails when nobody is watching the lobby.
+        if self.tx.send(event).is_err() {
+            debug!("lobby_event_unobserved");
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::sync::LazyLock;
+use tracing::warn;
+use ultimatexo_core::{BotLevel, RoomType};
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub fn metrics() -> &'static Metrics {
+    &METRICS
+}
+
+pub struct Metrics {
+    registry: Registry,
+    pub live_rooms: IntGaugeVec,
+    pub connected_players: IntGaugeVec,
+    /// Use `rate()` over this counter for moves per second.
+    pub moves: IntCounterVec,
+    pub bot_search_seconds: HistogramVec,
+    pub bot_nodes_searched: HistogramVec,
+    pub cleanup_timeouts: IntCounter,
+    pub ws_send_failures: IntCounter,
+    pub rate_limiter_keys: IntGauge,
+    pub events_dropped: IntCounterVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new_custom(Some("ultimatexo".into()), None)
+            .expect("metrics namespace is valid");
+
+        let live_rooms = IntGaugeVec::new(
+            Opts::new("live_rooms", "Rooms currently held in memory"),
+            &["room_type"],
+        )
+        .expect("valid metric");
+        let connected_players = IntGaugeVec::new(
+            Opts::new("connected_players", "Players with an open game socket"),
+            &["room_type"],
+        )
+        .expect("valid metric");
+        let moves = IntCounterVec::new(
+            Opts::new("moves_total", "Moves accepted by the game engine"),
+            &["actor"],
+        )
+        .expect("valid metric");
+        let bot_search_seconds = HistogramVec::new(
+            HistogramOpts::new("bot_search_seconds", "Wall time of a bot move search").buckets(
+                vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0],
+            ),
+            &["bot_level"],
+        )
+        .expect("valid metric");
+        let bot_nodes_searched = HistogramVec::new(
+            HistogramOpts::new("bot_nodes_searched", "Nodes visited by a bot move search")
+                .buckets(prometheus::exponential_buckets(100.0, 4.0, 10).expect("valid buckets")),
+            &["bot_level"],
+        )
+        .expect("valid metric");
+        let cleanup_timeouts = IntCounter::new(
+            "cleanup_timeouts_total",
+            "Rooms closed because a disconnected player never came back",
+        )
+        .expect("valid metric");
+        let ws_send_failures = IntCounter::new(
+            "ws_send_failures_total",
+            "Messages that could not be delivered to a player",
+        )
+        .expect("valid metric");
+        let rate_limiter_keys = IntGauge::new(
+            "rate_limiter_keys",
+            "Client keys tracked by the HTTP rate limiter",
+        )
+        .expect("valid metric");
+        let events_dropped = IntCounterVec::new(
+            Opts::new(
+                "events_dropped_total",
+                "Domain events a lagging bus subscriber skipped",
+            ),
+            &["subscriber"],
+        )
+        .expect("valid metric");
+
+        for collector in [
+            Box::new(live_rooms.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(connected_players.clone()),
+            Box::new(moves.clone()),
+            Box::new(bot_search_seconds.clone()),
+            Box::new(bot_nodes_searched.clone()),
+            Box::new(cleanup_timeouts.clone()),
+            Box::new(ws_send_failures.clone()),
+            Box::new(rate_limiter_keys.clone()),
+            Box::new(events_dropped.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("metric registered once");
+        }
+
+        Self {
+            registry,
+            live_rooms,
+            connected_players,
+            moves,
+            bot_search_seconds,
+            bot_nodes_searched,
+            cleanup_timeouts,
+            ws_send_failures,
+            rate_limiter_keys,
+            events_dropped,
+        }
+    }
+
+    pub fn set_room_occupancy(&self, room_type: &RoomType, rooms: usize, players: usize) {
+        let label = room_type_label(room_type);
+        self.live_rooms
+            .with_label_values(&[label])
+            .set(rooms as i64);
+        self.connected_players
+            .with_label_values(&[label])
+            .set(players as i64);
+    }
+
+    /// Counted where the move is applied; `actor` is `player` or `bot`.
+    pub fn record_move(&self, actor: &str) {
+        self.moves.with_label_values(&[actor]).inc();
+    }
+
+    pub fn observe_bot_search(&self, level: Option<&BotLevel>, seconds: f64, nodes: usize) {
+        let label = level.map_or("unknown", BotLevel::as_str);
+        self.bot_search_seconds
+            .with_label_values(&[label])
+            .observe(seconds);
+        self.bot_nodes_searched
+            .with_label_values(&[label])
+            .observe(nodes as f64);
+    }
+
+    /// Prometheus text exposition of every registered metric.
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            warn!(error = %e, "metrics_encode_failed");
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+fn room_type_label(room_type: &RoomType) -> &'static str {
+    match room_type {
+        RoomType::Standard => "standard",
+        RoomType::BotRoom => "bot",
+        RoomType::LocalRoom => "local",
+    }
+}
@@ -1,40 +1,126 @@
 use dashmap::DashMap;
-use std::sync::{Arc, atomic::Ordering};
+use std::{
+    sync::{Arc, atomic::Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
-use crate::CleanupService;
+use crate::{CleanupService, EventBus, GameAIService, LobbyFeed, metrics};
 use ultimatexo_core::{
-    domain::RoomRules,
+    config::BotSettings,
+    domain::{DomainEvent, Position, RoomLifecycleEvent, RoomPhase, RoomRules},
     error::AppError,
-    models::{PlayerAction, Room, RoomInfo, SerizlizedPlayer, ServerMessage, WebSocketQuery},
+    models::{
+        GetRoomQuery, PlayerAction, Room, RoomDetail, RoomInfo, RoomOverview, SerizlizedPlayer,
+        ServerMessage, WebSocketQuery,
+    },
 };
 
 pub struct RoomService {
     rooms: Arc<DashMap<String, Arc<Room>>>,
     rules: Arc<dyn RoomRules>,
     cleanup_service: CleanupService,
+    lobby: LobbyFeed,
+    events: EventBus,
+    bots: BotSettings,
 }
 
 impl RoomService {
-    pub fn with_rules(rules: Arc<dyn RoomRules>) -> Self {
-        Self {
+    pub fn with_rules(rules: Arc<dyn RoomRules>, events: EventBus, bots: BotSettings) -> Self {
+        let lobby = LobbyFeed::new();
+        let service = Self {
             rooms: Arc::new(DashMap::new()),
             rules,
-            cleanup_service: CleanupService::new(),
-        }
+            cleanup_service: CleanupService::new(lobby.clone(), events.clone()),
+            lobby,
+            events,
+            bots,
+        };
+        service.spawn_lobby_updates();
+        service
+    }
+
+    /// Tells the lobby whenever one of this service's rooms starts, pauses,
+    /// finishes or closes.
+    fn spawn_lobby_updates(&self) {
+        let rooms = self.rooms.clone();
+        let lobby = self.lobby.clone();
+        self.events.spawn_subscriber("lobby", move |event| {
+            let changed = match event {
+                DomainEvent::RoomTransitioned {
+                    room_id,
+                    transition,
+                } => rooms
+                    .get(&room_id)
+                    .map(|room| (room.clone(), transition.to)),
+                _ => None,
+            };
+            let lobby = lobby.clone();
+            async move {
+                match changed {
+                    Some((room, RoomPhase::Closing)) => lobby.room_removed(&room.info),
+                    Some((room, _)) => lobby.room_changed(&room.snapshot_info().await),
+                    None => {}
+                }
+            }
+        });
+    }
+
+    pub fn lobby(&self) -> &LobbyFeed {
+        &self.lobby
     }
 
     pub async fn create_room(&self, mut room_info: RoomInfo) -> Result<String, AppError> {
         let room_id = self.generate_room_id();
         room_info.id = room_id.clone();
         room_info.is_protected = room_info.password.is_some();
+        room_info.max_players = self.rules.get_max_players();
+        room_info.created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
 
         let (tx, rx) = mpsc::channel(32);
-        let room = Arc::new(Room::new(room_info, tx));
+        // A configured bot still gets a level, for its behaviour and metrics.
+        if let Some(config) = &room_info.bot_config {
+            room_info.bot_level = Some(self.bots.level_for(config.difficulty()));
+        }
+        let difficulty = match &room_info.bot_config {
+            Some(config) => Some(config.difficulty()),
+            None => room_info
+                .bot_level
+                .as_ref()
+                .map(|level| self.bots.difficulty_for(level)),
+        };
+        let strength = match &room_info.bot_config {
+            Some(config) => Some(GameAIService::configured(config)),
+            None => difficulty.map(|difficulty| GameAIService::strength(f64::from(difficulty))),
+        };
+        let bot = room_info
+            .bot_level
+            .as_ref()
+            .map(|level| self.bots.behaviour.get(level));
+        let start = room_info
+            .position
+            .as_deref()
+            .map(|text| Position::parse(text, room_info.rules))
+            .transpose()?;
+        let room = Arc::new(Room::new(
+            room_info,
+            tx,
+            self.events.sender(),
+            difficulty,
+            bot,
+            start,
+        ));
+        room.game.lock().await.state.bot_strength = strength;
         Room::spawn_message_broadcaster(room.clone(), rx);
 
+        let info = room.snapshot_info().await;
+        self.lobby.room_created(&info);
+        self.events.publish(DomainEvent::RoomCreated { room: info });
         self.rooms.insert(room_id.clone(), room);
         Ok(room_id)
     }
@@ -46,6 +132,12 @@ impl RoomService {
         client_hash: String,
     ) -> Result<(Arc<Room>, String), AppError> {
         let room = self.get_room(room_id)?;
+        room.ensure_open().await?;
+        if let Some(player_id) = &payload.player_id
+            && room.is_removed(player_id).await
+        {
+            return Err(AppError::player_removed());
+        }
         let current_count = room.get_player_count();
 
         if payload.is_reconnecting {
@@ -82,12 +174,11 @@ impl RoomService {
 
         let remaining_count = room.player_counter.fetch_sub(1, Ordering::SeqCst) - 1;
 
-        info!(
-            player_id = %player_id,
-            room_id = %room_id,
-            remaining_players = remaining_count,
-            "player_leaving"
-        );
+        self.events.publish(DomainEvent::PlayerLeft {
+            room_id: room_id.to_string(),
+            player_id: player_id.to_string(),
+            remaining_players: remaining_count,
+        });
 
         self.cancel_pending_cleanup(&room).await;
 
@@ -102,7 +193,9 @@ impl RoomService {
                 .remove_room_immediately(self.rooms.clone(), room, room_id)
                 .await;
         } else {
-            self.handle_player_disconnect(room, player_id).await?;
+            self.handle_player_disconnect(room.clone(), player_id)
+                .await?;
+            self.lobby.room_updated(&room.snapshot_info().await);
         }
 
         Ok(())
@@ -113,6 +206,10 @@ impl RoomService {
         room: Arc<Room>,
         leaving_player_id: &str,
     ) -> Result<(), AppError> {
+        if room
+            .transition_first(&[RoomLifecycleEvent::GamePaused])
+            .await
+            .is_some()
         {
             let mut game = room.game.lock().await;
             game.set_board_status(self.rules.get_disconnect_game_state());
@@ -138,14 +235,14 @@ impl RoomService {
                 opponent_id = %opponent.id,
                 "notify_disconnect_failed"
             );
+            metrics().ws_send_failures.inc();
         }
 
-        info!(
-            player_id = %leaving_player_id,
-            room_id = %room.info.id,
-            timeout_seconds = self.rules.get_cleanup_timeout().as_secs(),
-            "player_disconnected"
-        );
+        self.events.publish(DomainEvent::PlayerDisconnected {
+            room_id: room.info.id.clone(),
+            player_id: leaving_player_id.to_string(),
+            timeout_secs: self.rules.get_cleanup_timeout().as_secs(),
+        });
 
         self.schedule_cleanup(room, leaving_player_id).await;
 
@@ -155,7 +252,6 @@ impl RoomService {
     async fn cancel_pending_cleanup(&self, room: &Arc<Room>) {
         if let Some(token) = room.deletion_token.lock().await.take() {
             token.cancel();
-            debug!(room_id = %room.info.id, "cleanup_cancelled");
             info!(room_id = %room.info.id, "cleanup_cancelled");
         }
     }
@@ -181,20 +277,125 @@ impl RoomService {
             .await;
     }
 
-    pub fn get_public_rooms(&self, name_filter: Option<&str>) -> Vec<RoomInfo> {
+    pub async fn get_public_rooms(&self, query: &GetRoomQuery) -> (usize, Vec<RoomInfo>) {
+        let rooms: Vec<Arc<Room>> = self
+            .rooms
+            .iter()
+            .filter(|entry| entry.value().info.is_public)
+            .map(|entry| entry.value().clone())
+            .collect();
+
+        let mut infos = Vec::with_capacity(rooms.len());
+        for room in rooms {
+            infos.push(room.snapshot_info().await);
+        }
+        query.apply(infos)
+    }
+
+    pub fn room_count(&self) -> usize {
+        self.rooms.len()
+    }
+
+    pub fn connected_players(&self) -> usize {
         self.rooms
             .iter()
-            .map(|entry| entry.value().info.clone())
-            .filter(|room| room.is_public)
-            .filter(|room| name_filter.is_none_or(|filter| room.name.starts_with(filter)))
-            .collect()
+            .map(|entry| entry.value().get_player_count())
+            .sum()
+    }
+
+    pub async fn rooms_overview(&self) -> Vec<RoomOverview> {
+        let mut overviews = Vec::with_capacity(self.rooms.len());
+        for room in self.all_rooms() {
+            overviews.push(room.overview().await);
+        }
+        overviews
+    }
+
+    pub async fn room_detail(&self, room_id: &str) -> Result<RoomDetail, AppError> {
+        Ok(self.get_room(room_id)?.detail().await)
+    }
+
+    /// Notifies everyone in the room with `reason`, closes their sockets and
+    /// drops the room without waiting for the usual cleanup.
+    pub async fn close_room(&self, room_id: &str, reason: &str) -> Result<(), AppError> {
+        let room = self.get_room(room_id)?;
+        room.close_connections(reason).await;
+        self.cleanup_service
+            .remove_room_immediately(self.rooms.clone(), room, room_id)
+            .await;
+        Ok(())
+    }
+
+    pub async fn kick_player(
+        &self,
+        room_id: &str,
+        player_id: &str,
+        reason: &str,
+    ) -> Result<(), AppError> {
+        self.get_room(room_id)?
+            .remove_player(&player_id.to_string(), reason)
+            .await
+    }
+
+    /// Sends `message` to every player socket and lobby watcher of this
+    /// service. Returns how many player sockets were reached.
+    pub async fn announce(&self, message: &str) -> usize {
+        self.lobby.announce(message);
+        self.broadcast(ServerMessage::Announcement {
+            message: message.to_string(),
+        })
+        .await
+    }
+
+    pub async fn warn_shutdown(&self, remaining_secs: u64) -> usize {
+        self.lobby.shutdown_warning(remaining_secs);
+        self.broadcast(ServerMessage::ShutdownWarning { remaining_secs })
+            .await
+    }
+
+    /// Sends `message` to every player in every room of this service.
+    pub async fn broadcast(&self, message: ServerMessage) -> usize {
+        let mut reached = 0;
+        for room in self.all_rooms() {
+            reached += room.send_to_players(message.clone()).await;
+        }
+        reached
+    }
+
+    /// Rooms whose game is being played or waiting on a reconnection.
+    pub async fn active_games(&self) -> usize {
+        let mut active = 0;
+        for room in self.all_rooms() {
+            if matches!(room.phase().await, RoomPhase::Playing | RoomPhase::Paused) {
+                active += 1;
+            }
+        }
+        active
+    }
+
+    /// Publishes a `GameInterrupted` record for every game that has not
+    /// finished, so subscribers can persist it.
+    pub async fn snapshot_active_games(&self) -> usize {
+        let mut snapshots = 0;
+        for room in self.all_rooms() {
+            if matches!(room.phase().await, RoomPhase::Playing | RoomPhase::Paused) {
+                let record = room.game_record().await;
+                self.events.publish(DomainEvent::GameInterrupted { record });
+                snapshots += 1;
+            }
+        }
+        snapshots
     }
 
-    pub fn get_room_info(&self, room_id: &str) -> Result<RoomInfo, AppError> {
+    fn all_rooms(&self) -> Vec<Arc<Room>> {
         self.rooms
-            .get(room_id)
-            .map(|room| room.info.clone())
-            .ok_or(AppError::room_not_found())
+            .iter()
+            .map(|entry| entry.value().clone())
+            .collect()
+    }
+
+    pub async fn get_room_info(&self, room_id: &str) -> Result<RoomInfo, AppError> {
+        Ok(self.get_room(room_id)?.snapshot_info().await)
     }
 
     fn get_room(&self, room_id: &str) -> Result<Arc<Room>, AppError> {
@@ -224,12 +425,12 @@ impl RoomService {
             if let Some(token) = room.deletion_token.lock().await.take() {
                 token.cancel();
             }
-            info!(
-                player_id = %player_id,
-                room_id = %room.info.id,
-                "player_reconnected"
-            );
+            self.events.publish(DomainEvent::PlayerReconnected {
+                room_id: room.info.id.clone(),
+                player_id: player_id.clone(),
+            });
             room.player_counter.fetch_add(1, Ordering::SeqCst);
+            self.publish_occupancy(&room).await;
             Ok((room, player_id))
         } else {
             Err(AppError::player_not_found())
@@ -243,12 +444,95 @@ impl RoomService {
         client_hash: String,
     ) -> Result<(Arc<Room>, String), AppError> {
         let new_player_id = room.add_player(player_id).await?;
-        info!(
-            player_id = %new_player_id,
-            room_id = %room.info.id,
-            client_hash = %client_hash,
-            "player_joined"
-        );
+        room.transition_first(&[RoomLifecycleEvent::Opened]).await;
+        let marker = room.get_player(&new_player_id).await?.info.marker;
+        self.events.publish(DomainEvent::PlayerJoined {
+            room_id: room.info.id.clone(),
+            player_id: new_player_id.clone(),
+            marker,
+            client_hash,
+        });
+        self.publish_occupancy(&room).await;
         Ok((room, new_player_id))
     }
+
+    async fn publish_occupancy(&self, room: &Room) {
+        self.lobby.room_changed(&room.snapshot_info().await);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time::timeout;
+    use ultimatexo_core::{StandardRoomRules, models::LobbyEvent};
+
+    fn join_query() -> WebSocketQuery {
+        WebSocketQuery {
+            password: None,
+            is_reconnecting: false,
+            player_id: None,
+            version: None,
+            encoding: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn starting_a_game_removes_the_room_from_lobbies_hiding_games_in_progress() {
+        let service = RoomService::with_rules(
+            Arc::new(StandardRoomRules::new(Duration::from_secs(60))),
+            EventBus::new(),
+            BotSettings::default(),
+        );
+        let mut lobby = service.lobby().subscribe();
+        let query = GetRoomQuery {
+            hide_full: false,
+            hide_in_progress: true,
+            ..GetRoomQuery::default()
+        };
+        let room_id = service
+            .create_room(RoomInfo {
+                name: "lobby".to_string(),
+                is_public: true,
+                ..RoomInfo::default()
+            })
+            .await
+            .unwrap();
+        for hash in ["a", "b"] {
+            service
+                .join_room(&room_id, join_query(), hash.to_string())
+                .await
+                .unwrap();
+        }
+
+        // Filling the room alone does not hide it.
+        let removed_early = timeout(Duration::from_millis(200), async {
+            loop {
+                let event = lobby.recv().await.unwrap();
+                if let Some(LobbyEvent::RoomRemoved { .. }) = event.for_query(&query) {
+                    return;
+                }
+            }
+        })
+        .await;
+        assert!(removed_early.is_err());
+
+        let room = service.get_room(&room_id).unwrap();
+        assert!(room.start_or_resume().await.unwrap().is_some());
+        let removed = timeout(Duration::from_secs(1), async {
+            loop {
+                match lobby.recv().await.unwrap().for_query(&query) {
+                    Some(LobbyEvent::RoomRemoved { id }) => return id,
+                    Some(LobbyEvent::RoomUpdated(info) | LobbyEvent::RoomFilled(info)) => {
+                        panic!("room still listed as {:?}", info.game_status)
+                    }
+                    _ => {}
+                }
+            }
+        })
+        .await
+        .expect("no RoomRemoved after the game started");
+        assert_eq!(removed, room_id);
+    }
 }
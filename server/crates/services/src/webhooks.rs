@@ -0,0 +1,510 @@
+use crate::EventBus;
+use hmac::{Hmac, KeyInit, Mac};
+use serde::Serialize;
+use serde_json::{Value, json};
+use sha2::Sha256;
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+use ultimatexo_core::{
+    config::{ConfigError, WebhookSettings},
+    domain::DomainEvent,
+    models::GameRecord,
+};
+use uuid::Uuid;
+
+const SIGNATURE_HEADER: &str = "X-UltimateXO-Signature";
+const EVENT_HEADER: &str = "X-UltimateXO-Event";
+const DELIVERY_HEADER: &str = "X-UltimateXO-Delivery";
+const TIMESTAMP_HEADER: &str = "X-UltimateXO-Timestamp";
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    GameFinished,
+    GameInterrupted,
+    RoomCreated,
+    PlayerJoined,
+    PlayerTimeout,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 5] = [
+        WebhookEvent::GameFinished,
+        WebhookEvent::GameInterrupted,
+        WebhookEvent::RoomCreated,
+        WebhookEvent::PlayerJoined,
+        WebhookEvent::PlayerTimeout,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::GameFinished => "game_finished",
+            WebhookEvent::GameInterrupted => "game_interrupted",
+            WebhookEvent::RoomCreated => "room_created",
+            WebhookEvent::PlayerJoined => "player_joined",
+            WebhookEvent::PlayerTimeout => "player_timeout",
+        }
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.as_str() == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub urls: Vec<String>,
+    pub secret: String,
+    pub events: Vec<WebhookEvent>,
+    pub max_attempts: u32,
+    pub timeout: Duration,
+}
+
+impl WebhookConfig {
+    /// `Ok(None)` when no webhook URLs are configured.
+    pub fn from_settings(settings: &WebhookSettings) -> Result<Option<Self>, ConfigError> {
+        if settings.urls.is_empty() {
+            return Ok(None);
+        }
+        let Some(secret) = settings.secret.clone() else {
+            return Err(ConfigError::Invalid(vec![
+                "webhooks.secret is required when webhooks.urls is set".to_string(),
+            ]));
+        };
+
+        let events = match &settings.events {
+            Some(names) => {
+                let mut events = Vec::with_capacity(names.len());
+                let mut unknown = Vec::new();
+                for name in names {
+                    match WebhookEvent::parse(name) {
+                        Some(event) => events.push(event),
+                        None => unknown.push(format!(
+                            "webhooks.events entry {name:?} is not one of {}",
+                            WebhookEvent::ALL.map(|e| e.as_str()).join(", ")
+                        )),
+                    }
+                }
+                if !unknown.is_empty() {
+                    return Err(ConfigError::Invalid(unknown));
+                }
+                events
+            }
+            None => WebhookEvent::ALL.to_vec(),
+        };
+
+        Ok(Some(Self {
+            urls: settings.urls.clone(),
+            secret,
+            events,
+            max_attempts: settings.max_attempts,
+            timeout: Duration::from_secs(settings.timeout_secs),
+        }))
+    }
+}
+
+#[derive(Serialize)]
+struct WebhookPayload {
+    id: String,
+    event: WebhookEvent,
+    created_at: u64,
+    data: Value,
+}
+
+/// Subscribes to the event bus and POSTs the selected events to every
+/// configured URL. Each delivery retries independently with exponential
+/// backoff, so a slow receiver never holds up the others.
+pub fn spawn_webhooks(bus: &EventBus, config: WebhookConfig) -> JoinHandle<()> {
+    let client = match reqwest::Client::builder().timeout(config.timeout).build() {
+        Ok(client) => client,
+        Err(e) => {
+            warn!(error = %e, "webhook_client_failed");
+            return tokio::spawn(async {});
+        }
+    };
+    info!(
+        targets = config.urls.len(),
+        events = ?config.events,
+        "webhooks_enabled"
+    );
+    let config = Arc::new(config);
+
+    let deliveries = bus.clone();
+    bus.spawn_subscriber("webhooks", move |event| {
+        let client = client.clone();
+        let deliveries = deliveries.clone();
+        let config = config.clone();
+        async move {
+            let Some((event, data)) = webhook_for(&event, &config.secret) else {
+                return;
+            };
+            if !config.events.contains(&event) {
+                return;
+            }
+
+            let payload = WebhookPayload {
+                id: Uuid::new_v4().to_string(),
+                event,
+                created_at: unix_now(),
+                data,
+            };
+            let body = match serde_json::to_vec(&payload) {
+                Ok(body) => body,
+                Err(e) => {
+                    warn!(event = event.as_str(), error = %e, "webhook_serialize_failed");
+                    return;
+                }
+            };
+
+            for url in &config.urls {
+                deliveries.spawn_tracked(deliver(
+                    client.clone(),
+                    url.clone(),
+                    event,
+                    payload.id.clone(),
+                    body.clone(),
+                    config.secret.clone(),
+                    config.max_attempts,
+                ));
+            }
+        }
+    })
+}
+
+/// Player ids are reconnect credentials, so receivers get a keyed hash of
+/// them instead: stable per player, but useless for taking over a seat.
+fn webhook_for(event: &DomainEvent, secret: &str) -> Option<(WebhookEvent, Value)> {
+    let mapped = match event {
+        DomainEvent::GameFinished { record } => (
+            WebhookEvent::GameFinished,
+            json!(redact_record(record, secret)),
+        ),
+        DomainEvent::GameInterrupted { record } => (
+            WebhookEvent::GameInterrupted,
+            json!(redact_record(record, secret)),
+        ),
+        DomainEvent::RoomCreated { room } => (WebhookEvent::RoomCreated, json!(room)),
+        DomainEvent::PlayerJoined {
+            room_id,
+            player_id,
+            marker,
+            ..
+        } => (
+            WebhookEvent::PlayerJoined,
+            json!({
+                "room_id": room_id,
+                "player_id": player_ref(secret, player_id),
+                "marker": marker,
+            }),
+        ),
+        DomainEvent::PlayerTimedOut {
+            room_id,
+            player_id,
+            result,
+        } => (
+            WebhookEvent::PlayerTimeout,
+            json!({
+                "room_id": room_id,
+                "player_id": player_ref(secret, player_id),
+                "result": result,
+            }),
+        ),
+        _ => return None,
+    };
+    Some(mapped)
+}
+
+fn redact_record(record: &GameRecord, secret: &str) -> GameRecord {
+    let mut record = record.clone();
+    for player in &mut record.players {
+        player.id = player.id.as_deref().map(|id| player_ref(secret, id));
+    }
+    record
+}
+
+fn player_ref(secret: &str, player_id: &str) -> String {
+    let mut digest = hmac_hex(secret, &[b"player:", player_id.as_bytes()]);
+    digest.truncate(16);
+    digest
+}
+
+/// Hex-encoded HMAC-SHA256 of `<timestamp>.<raw body>`, sent as
+/// `sha256=<hex>`. Receivers should reject timestamps too far from their
+/// clock so a captured delivery cannot be replayed.
+pub fn sign(secret: &str, timestamp: u64, body: &[u8]) -> String {
+    let timestamp = timestamp.to_string();
+    let digest = hmac_hex(secret, &[timestamp.as_bytes(), b".", body]);
+    format!("sha256={digest}")
+}
+
+fn hmac_hex(secret: &str, parts: &[&[u8]]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    for part in parts {
+        mac.update(part);
+    }
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+async fn deliver(
+    client: reqwest::Client,
+    url: String,
+    event: WebhookEvent,
+    delivery_id: String,
+    body: Vec<u8>,
+    secret: String,
+    max_attempts: u32,
+) {
+    for attempt in 1..=max_attempts {
+        // Signed per attempt, so a retry carries a fresh timestamp.
+        let timestamp = unix_now();
+        let result = client
+            .post(&url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(EVENT_HEADER, event.as_str())
+            .header(DELIVERY_HEADER, &delivery_id)
+            .header(TIMESTAMP_HEADER, timestamp)
+            .header(SIGNATURE_HEADER, sign(&secret, timestamp, &body))
+            .body(body.clone())
+            .send()
+            .await;
+
+        let retryable = match result {
+            Ok(response) if response.status().is_success() => {
+                debug!(
+                    url = %url,
+                    event = event.as_str(),
+                    delivery_id = %delivery_id,
+                    attempt,
+                    "webhook_delivered"
+                );
+                return;
+            }
+            Ok(response) => {
+                let status = response.status();
+                warn!(
+                    url = %url,
+                    event = event.as_str(),
+                    delivery_id = %delivery_id,
+                    attempt,
+                    status = status.as_u16(),
+                    "webhook_rejected"
+                );
+                status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+            }
+            Err(e) => {
+                warn!(
+                    url = %url,
+                    event = event.as_str(),
+                    delivery_id = %delivery_id,
+                    attempt,
+                    error = %e,
+                    "webhook_request_failed"
+                );
+                true
+            }
+        };
+
+        if !retryable || attempt == max_attempts {
+            break;
+        }
+        tokio::time::sleep(backoff(attempt)).await;
+    }
+
+    warn!(
+        url = %url,
+        event = event.as_str(),
+        delivery_id = %delivery_id,
+        "webhook_dropped"
+    );
+}
+
+fn backoff(attempt: u32) -> Duration {
+    BASE_BACKOFF
+        .saturating_mul(2u32.saturating_pow(attempt - 1))
+        .min(MAX_BACKOFF)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{Router, body::Bytes, http::HeaderMap, routing::post};
+    use std::{collections::VecDeque, sync::Mutex, time::Instant};
+    use tokio::net::TcpListener;
+    use ultimatexo_core::{Marker, RoomInfo};
+
+    const SECRET: &str = "secret";
+
+    struct Received {
+        at: Instant,
+        headers: HeaderMap,
+        body: Bytes,
+    }
+
+    type Inbox = Arc<Mutex<Vec<Received>>>;
+
+    /// A receiver that answers with `statuses` in order, then 200.
+    async fn receiver(statuses: Vec<u16>) -> (String, Inbox) {
+        let inbox: Inbox = Arc::default();
+        let statuses = Arc::new(Mutex::new(VecDeque::from(statuses)));
+        let app = Router::new().route(
+            "/",
+            post({
+                let inbox = inbox.clone();
+                move |headers: HeaderMap, body: Bytes| async move {
+                    inbox.lock().unwrap().push(Received {
+                        at: Instant::now(),
+                        headers,
+                        body,
+                    });
+                    let status = statuses.lock().unwrap().pop_front().unwrap_or(200);
+                    axum::http::StatusCode::from_u16(status).unwrap()
+                }
+            }),
+        );
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, inbox)
+    }
+
+    fn config(url: String, events: Vec<WebhookEvent>, max_attempts: u32) -> WebhookConfig {
+        WebhookConfig {
+            urls: vec![url],
+            secret: SECRET.to_string(),
+            events,
+            max_attempts,
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    fn player_joined(player_id: &str) -> DomainEvent {
+        DomainEvent::PlayerJoined {
+            room_id: "123456".to_string(),
+            player_id: player_id.to_string(),
+            marker: Marker::X,
+            client_hash: "hash".to_string(),
+        }
+    }
+
+    async fn wait_for(inbox: &Inbox, count: usize) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while inbox.lock().unwrap().len() < count && Instant::now() < deadline {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    fn header<'a>(received: &'a Received, name: &str) -> &'a str {
+        received.headers[name].to_str().unwrap()
+    }
+
+    #[test]
+    fn signs_timestamp_and_body() {
+        assert_eq!(
+            sign(SECRET, 1_700_000_000, br#"{"ok":true}"#),
+            "sha256=c1afc7c2df3db0690d7d75954610ed1a1d959ce96355ccb8c0a8bc09fd0cfc27"
+        );
+        assert_ne!(
+            sign(SECRET, 1_700_000_001, br#"{"ok":true}"#),
+            sign(SECRET, 1_700_000_000, br#"{"ok":true}"#)
+        );
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_cap() {
+        assert_eq!(backoff(1), Duration::from_millis(500));
+        assert_eq!(backoff(2), Duration::from_secs(1));
+        assert_eq!(backoff(3), Duration::from_secs(2));
+        assert_eq!(backoff(40), MAX_BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn delivery_is_signed_and_hides_player_ids() {
+        let (url, inbox) = receiver(Vec::new()).await;
+        let bus = EventBus::new();
+        spawn_webhooks(&bus, config(url, WebhookEvent::ALL.to_vec(), 1));
+        bus.publish(player_joined("raw-player-id"));
+        wait_for(&inbox, 1).await;
+
+        let inbox = inbox.lock().unwrap();
+        let delivery = &inbox[0];
+        let timestamp: u64 = header(delivery, TIMESTAMP_HEADER).parse().unwrap();
+        assert_eq!(
+            header(delivery, SIGNATURE_HEADER),
+            sign(SECRET, timestamp, &delivery.body)
+        );
+        assert_eq!(header(delivery, EVENT_HEADER), "player_joined");
+
+        let payload: Value = serde_json::from_slice(&delivery.body).unwrap();
+        assert_eq!(
+            payload["data"]["player_id"],
+            player_ref(SECRET, "raw-player-id")
+        );
+        assert!(!String::from_utf8_lossy(&delivery.body).contains("raw-player-id"));
+    }
+
+    #[tokio::test]
+    async fn retries_server_errors_with_backoff() {
+        let (url, inbox) = receiver(vec![500, 503]).await;
+        let bus = EventBus::new();
+        spawn_webhooks(&bus, config(url, WebhookEvent::ALL.to_vec(), 3));
+        bus.publish(player_joined("player"));
+        wait_for(&inbox, 3).await;
+
+        let inbox = inbox.lock().unwrap();
+        assert_eq!(inbox.len(), 3);
+        assert!(inbox[1].at - inbox[0].at >= backoff(1));
+        assert!(inbox[2].at - inbox[1].at >= backoff(2));
+        let ids: Vec<&str> = inbox.iter().map(|r| header(r, DELIVERY_HEADER)).collect();
+        assert!(ids.iter().all(|id| *id == ids[0]));
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let (url, inbox) = receiver(vec![400]).await;
+        let bus = EventBus::new();
+        spawn_webhooks(&bus, config(url, WebhookEvent::ALL.to_vec(), 3));
+        bus.publish(player_joined("player"));
+        wait_for(&inbox, 1).await;
+        tokio::time::sleep(backoff(1) * 2).await;
+
+        assert_eq!(inbox.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn delivers_only_selected_events() {
+        let (url, inbox) = receiver(Vec::new()).await;
+        let bus = EventBus::new();
+        spawn_webhooks(&bus, config(url, vec![WebhookEvent::PlayerJoined], 1));
+        bus.publish(DomainEvent::RoomCreated {
+            room: RoomInfo::default(),
+        });
+        bus.publish(DomainEvent::RoomRemoved {
+            room_id: "123456".to_string(),
+        });
+        bus.publish(player_joined("player"));
+        wait_for(&inbox, 1).await;
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let inbox = inbox.lock().unwrap();
+        assert_eq!(inbox.len(), 1);
+        assert_eq!(header(&inbox[0], EVENT_HEADER), "player_joined");
+    }
+}